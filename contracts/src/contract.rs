@@ -1,23 +1,38 @@
 use crate::error::ContractError;
 use crate::handlers::{
-    execute_extend_subdomain_expiry, execute_register, execute_remove_subdomain,
-    execute_renew_registration, execute_set_subdomain, execute_update_config,
-    execute_update_resolver, execute_user_metadata_update, execute_withdraw_fees,
+    execute_apply_dispute, execute_backfill_indexes, execute_batch_register, execute_batch_renew,
+    execute_burn_subdomain_fuses, execute_cancel_dispute, execute_deposit_renewal_funds,
+    execute_extend_subdomain_expiry, execute_fund_cleanup_pool, execute_process_renewals,
+    execute_prune_expired, execute_reassign_name, execute_reconcile, execute_register,
+    execute_remove_subdomain, execute_renew_registration, execute_revoke_name,
+    execute_set_dns_mode, execute_set_dns_records, execute_set_reserved_names,
+    execute_set_subdomain, execute_set_subdomain_sale, execute_set_tld, execute_sync_ownership,
+    execute_update_config, execute_update_resolver, execute_user_metadata_update,
+    execute_withdraw_fees, execute_withdraw_renewal_funds,
 };
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SearchScope};
+use crate::parser::NameParser;
 use crate::read_utils::{
-    format_name, query_resolver, query_resolver_address, query_resolver_expiration,
+    query_availability, query_batch_availability, query_cleanup_pool, query_config, query_dns_zone,
+    query_error, query_expiring_between, query_name_info, query_names_by_owner,
+    query_pending_dispute, query_renewal_funds, query_resolver, query_resolver_address,
+    query_resolver_expiration, query_search_prefix, query_subdomain_fuses, query_subdomain_sale,
+    query_subdomains, query_tlds, query_verify_consistency,
 };
-use crate::state::{config, config_read, Config};
+use crate::state::{config, Config, NamingRules, DEFAULT_RENEWAL_WINDOW};
+use crate::write_utils::BURN_REPLY_ID;
 
 use archid_token::Metadata;
 
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response,
-    StdError, StdResult, SubMsgResult,
+    Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult,
+    SubMsgResult, Uint128,
 };
 
 use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
 
 pub type NameExtension = Option<Metadata>;
 
@@ -39,6 +54,15 @@ pub fn instantiate(
         cw721: msg.cw721,
         base_cost: msg.base_cost,
         base_expiration: msg.base_expiration,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: DEFAULT_RENEWAL_WINDOW,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
     };
     config(deps.storage).save(&config_state)?;
     Ok(Response::default())
@@ -51,13 +75,34 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    let parser = NameParser::load(deps.storage)?;
     match msg {
-        ExecuteMsg::Register { name } => execute_register(deps, env, info, format_name(name)),
+        ExecuteMsg::Register { name } => {
+            execute_register(deps, env, info, parser.normalize(&name)?)
+        }
         ExecuteMsg::RenewRegistration { name } => {
-            execute_renew_registration(deps, env, info, format_name(name))
+            execute_renew_registration(deps, env, info, parser.normalize(&name)?)
         }
+        ExecuteMsg::BatchRegister { names } => execute_batch_register(
+            deps,
+            env,
+            info,
+            names
+                .into_iter()
+                .map(|name| parser.normalize(&name))
+                .collect::<Result<Vec<String>, ContractError>>()?,
+        ),
+        ExecuteMsg::BatchRenew { names } => execute_batch_renew(
+            deps,
+            env,
+            info,
+            names
+                .into_iter()
+                .map(|name| parser.normalize(&name))
+                .collect::<Result<Vec<String>, ContractError>>()?,
+        ),
         ExecuteMsg::UpdateResolver { name, new_resolver } => {
-            execute_update_resolver(info, deps, env, format_name(name), new_resolver)
+            execute_update_resolver(info, deps, env, parser.normalize(&name)?, new_resolver)
         }
         ExecuteMsg::RegisterSubdomain {
             domain,
@@ -69,8 +114,8 @@ pub fn execute(
             info,
             deps,
             env,
-            format_name(domain),
-            subdomain,
+            parser.normalize(&domain)?,
+            parser.parse_label(&subdomain)?,
             new_resolver,
             new_owner,
             expiration,
@@ -83,39 +128,210 @@ pub fn execute(
             info,
             deps,
             env,
-            format_name(domain),
-            subdomain,
+            parser.normalize(&domain)?,
+            parser.parse_label(&subdomain)?,
             expiration,
         ),
         ExecuteMsg::UpdateUserDomainData {
             name,
             metadata_update,
-        } => execute_user_metadata_update(info, deps, format_name(name), metadata_update),
+        } => execute_user_metadata_update(info, deps, parser.normalize(&name)?, metadata_update),
 
         ExecuteMsg::UpdateConfig { config } => execute_update_config(deps, env, info, config),
 
-        ExecuteMsg::Withdraw { amount } => execute_withdraw_fees(info, deps, amount),
+        ExecuteMsg::Withdraw { amount, denom } => {
+            execute_withdraw_fees(info, deps, env, amount, denom)
+        }
+
+        ExecuteMsg::RemoveSubdomain { domain, subdomain } => execute_remove_subdomain(
+            info,
+            deps,
+            env,
+            parser.normalize(&domain)?,
+            parser.parse_label(&subdomain)?,
+        ),
+
+        ExecuteMsg::SetSubdomainSale { domain, sale } => {
+            execute_set_subdomain_sale(info, deps, env, parser.normalize(&domain)?, sale)
+        }
+
+        ExecuteMsg::BurnSubdomainFuses {
+            domain,
+            subdomain,
+            fuses,
+        } => execute_burn_subdomain_fuses(
+            info,
+            deps,
+            env,
+            parser.normalize(&domain)?,
+            parser.parse_label(&subdomain)?,
+            fuses,
+        ),
 
-        ExecuteMsg::RemoveSubdomain { domain, subdomain } => {
-            execute_remove_subdomain(info, deps, env, format_name(domain), subdomain)
+        ExecuteMsg::DepositRenewalFunds { name } => {
+            execute_deposit_renewal_funds(deps, env, info, parser.normalize(&name)?)
+        }
+        ExecuteMsg::WithdrawRenewalFunds { name, amount } => {
+            execute_withdraw_renewal_funds(deps, info, parser.normalize(&name)?, amount)
+        }
+        ExecuteMsg::ProcessRenewals { start_after, limit } => execute_process_renewals(
+            deps,
+            env,
+            info,
+            start_after
+                .map(|name| parser.normalize(&name))
+                .transpose()?,
+            limit,
+        ),
+        ExecuteMsg::FundCleanupPool {} => execute_fund_cleanup_pool(deps, info),
+        ExecuteMsg::PruneExpired { limit } => execute_prune_expired(deps, env, info, limit),
+        ExecuteMsg::SyncOwnership { name } => {
+            execute_sync_ownership(deps, env, parser.normalize(&name)?)
+        }
+        ExecuteMsg::Reconcile { names } => execute_reconcile(
+            deps,
+            env,
+            info,
+            names
+                .into_iter()
+                .map(|name| parser.normalize(&name))
+                .collect::<Result<Vec<String>, ContractError>>()?,
+        ),
+        ExecuteMsg::SetReservedNames { names, reserved } => execute_set_reserved_names(
+            deps,
+            env,
+            info,
+            names
+                .into_iter()
+                .map(|name| parser.normalize(&name))
+                .collect::<Result<Vec<String>, ContractError>>()?,
+            reserved,
+        ),
+        ExecuteMsg::BackfillIndexes { start_after, limit } => execute_backfill_indexes(
+            deps,
+            env,
+            info,
+            start_after
+                .map(|name| parser.normalize(&name))
+                .transpose()?,
+            limit,
+        ),
+        ExecuteMsg::RevokeName { name, reason } => {
+            execute_revoke_name(deps, env, info, parser.normalize(&name)?, reason)
+        }
+        ExecuteMsg::ReassignName { name, new_owner } => {
+            execute_reassign_name(deps, env, info, parser.normalize(&name)?, new_owner)
+        }
+        ExecuteMsg::ExecuteDispute { name } => {
+            execute_apply_dispute(deps, env, info, parser.normalize(&name)?)
+        }
+        ExecuteMsg::CancelDispute { name } => {
+            execute_cancel_dispute(deps, env, info, parser.normalize(&name)?)
+        }
+        ExecuteMsg::SetTld { tld, config } => {
+            execute_set_tld(deps, env, info, tld.to_ascii_lowercase(), config)
+        }
+        ExecuteMsg::SetDnsMode { name, enabled } => {
+            execute_set_dns_mode(deps, env, info, parser.normalize(&name)?, enabled)
+        }
+        ExecuteMsg::SetDnsRecords { name, records } => {
+            execute_set_dns_records(deps, env, info, parser.normalize(&name)?, records)
         }
     }
 }
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let parser = NameParser::load(deps.storage)?;
+    let normalize = |name: &str| parser.normalize(name).map_err(query_error);
     match msg {
-        QueryMsg::ResolveRecord { name } => query_resolver(deps, env, name),
+        QueryMsg::ResolveRecord { name } => query_resolver(deps, env, normalize(&name)?),
         QueryMsg::ResolveAddress { address } => query_resolver_address(deps, env, address),
-        QueryMsg::RecordExpiration { name } => query_resolver_expiration(deps, env, name),
-        QueryMsg::Config {} => to_json_binary(&config_read(deps.storage).load()?),
+        QueryMsg::RecordExpiration { name } => {
+            query_resolver_expiration(deps, env, normalize(&name)?)
+        }
+        QueryMsg::Config {} => query_config(deps, env),
+        QueryMsg::SubdomainSale { domain } => query_subdomain_sale(deps, env, normalize(&domain)?),
+        QueryMsg::SubdomainFuses { name } => query_subdomain_fuses(deps, env, normalize(&name)?),
+        QueryMsg::RenewalFunds { name } => query_renewal_funds(deps, env, normalize(&name)?),
+        QueryMsg::ExpiringBetween {
+            from,
+            to,
+            start_after,
+            limit,
+        } => query_expiring_between(
+            deps,
+            env,
+            from,
+            to,
+            start_after.as_deref().map(normalize).transpose()?,
+            limit,
+        ),
+        QueryMsg::NameInfo { name } => query_name_info(deps, env, normalize(&name)?),
+        QueryMsg::VerifyConsistency { start_after, limit } => {
+            query_verify_consistency(deps, env, start_after, limit)
+        }
+        QueryMsg::PendingDispute { name } => query_pending_dispute(deps, env, normalize(&name)?),
+        QueryMsg::Subdomains {
+            domain,
+            start_after,
+            limit,
+            include_expired,
+        } => query_subdomains(
+            deps,
+            env,
+            normalize(&domain)?,
+            start_after,
+            limit,
+            include_expired,
+        ),
+        QueryMsg::Tlds {} => query_tlds(deps, env),
+        QueryMsg::CleanupPool {} => query_cleanup_pool(deps, env),
+        QueryMsg::DnsZone { name } => query_dns_zone(deps, env, normalize(&name)?),
+        QueryMsg::Availability { name } => query_availability(deps, env, &parser, name),
+        QueryMsg::BatchAvailability { names } => {
+            query_batch_availability(deps, env, &parser, names)
+        }
+        QueryMsg::NamesByOwner {
+            owner,
+            start_after,
+            limit,
+            include_expired,
+        } => query_names_by_owner(deps, env, owner, start_after, limit, include_expired),
+        QueryMsg::SearchPrefix {
+            prefix,
+            scope,
+            start_after,
+            limit,
+            include_expired,
+        } => {
+            let scope = match scope.unwrap_or(SearchScope::All) {
+                SearchScope::Subdomains { parent } => SearchScope::Subdomains {
+                    parent: normalize(&parent)?,
+                },
+                scope => scope,
+            };
+            query_search_prefix(
+                deps,
+                env,
+                prefix,
+                scope,
+                start_after,
+                limit,
+                include_expired,
+            )
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
-    match msg.result {
-        SubMsgResult::Ok(_) => Ok(Response::default()),
-        SubMsgResult::Err(_) => Err(ContractError::Unauthorized {}),
+    match (msg.id, msg.result) {
+        (_, SubMsgResult::Ok(_)) => Ok(Response::default()),
+        // the token is already gone, the registry records are removed anyway
+        (BURN_REPLY_ID, SubMsgResult::Err(err)) => {
+            Ok(Response::new().add_attribute("burn_failed", err))
+        }
+        (_, SubMsgResult::Err(_)) => Err(ContractError::Unauthorized {}),
     }
 }
 
@@ -127,9 +343,16 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
     if original_version.contract != name {
         return Err(ContractError::InvalidInput {});
     }
-    if original_version.version >= version {
+    // compared as semver, as strings 1.10.0 would sort before 1.9.0
+    let parse = |version: &str| {
+        Version::parse(version).map_err(|err| StdError::generic_err(err.to_string()))
+    };
+    if parse(&original_version.version)? >= parse(&version)? {
         return Err(ContractError::InvalidInput {});
     }
+    // names saved before the expiration, subdomain and owner indexes existed
+    // are indexed afterwards through BackfillIndexes, which queries every
+    // name's owner and would not fit in a single transaction
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::default())
 }
@@ -0,0 +1,234 @@
+// DNS compatibility for names served through a DNS gateway. Domain owners can
+// opt in to LDH labels (letters, digits and hyphens) for their domain and its
+// subdomains, and store the record sets a gateway serves for their names.
+// Records render the way they appear in a zone file, e.g. `300 MX 10 mail.example.com.`
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use cosmwasm_std::StdError;
+
+use crate::error::ContractError;
+use crate::idn::ACE_PREFIX;
+use crate::state::{DnsRecord, DnsRecordData};
+
+pub const MAX_DNS_LABEL_LENGTH: usize = 63;
+pub const MAX_DNS_NAME_LENGTH: usize = 253;
+// a single TXT character-string
+pub const MAX_TXT_LENGTH: usize = 255;
+pub const MAX_DNS_RECORDS: usize = 20;
+// TTLs are 31 bit unsigned (RFC 2181)
+pub const MAX_TTL: u32 = 2147483647;
+
+/// validate_ldh_label returns an error unless an ascii label is DNS compatible,
+/// `--` is only allowed as the punycode prefix
+pub fn validate_ldh_label(label: &str) -> Result<(), ContractError> {
+    let is_ldh = !label.is_empty()
+        && label.len() <= MAX_DNS_LABEL_LENGTH
+        && label
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && (!label.contains("--") || label.starts_with(ACE_PREFIX));
+    match is_ldh {
+        true => Ok(()),
+        false => Err(ContractError::NotDnsCompatible {
+            label: label.to_string(),
+        }),
+    }
+}
+
+/// validate_dns_name checks every label of a full ascii name
+pub fn validate_dns_name(name: &str) -> Result<(), ContractError> {
+    if name.len() > MAX_DNS_NAME_LENGTH {
+        return Err(ContractError::NotDnsCompatible {
+            label: name.to_string(),
+        });
+    }
+    name.split('.').try_for_each(validate_ldh_label)
+}
+
+/// normalize_records validates a record set, returning it with host names
+/// lowercased and without their trailing `.`
+pub fn normalize_records(records: Vec<DnsRecord>) -> Result<Vec<DnsRecord>, ContractError> {
+    if records.len() > MAX_DNS_RECORDS {
+        return Err(ContractError::TooManyDnsRecords {
+            count: records.len() as u64,
+            max: MAX_DNS_RECORDS as u64,
+        });
+    }
+    let records = records
+        .into_iter()
+        .map(normalize_record)
+        .collect::<Result<Vec<DnsRecord>, ContractError>>()?;
+    // a CNAME can't be combined with any other record
+    if records.len() > 1 {
+        if let Some(cname) = records
+            .iter()
+            .find(|record| matches!(record.data, DnsRecordData::Cname { .. }))
+        {
+            return Err(ContractError::InvalidDnsRecord {
+                record: cname.to_string(),
+            });
+        }
+    }
+    Ok(records)
+}
+
+fn normalize_record(record: DnsRecord) -> Result<DnsRecord, ContractError> {
+    let invalid = |record: &DnsRecord| ContractError::InvalidDnsRecord {
+        record: record.to_string(),
+    };
+    if record.ttl == 0 || record.ttl > MAX_TTL {
+        return Err(invalid(&record));
+    }
+    let data = match &record.data {
+        DnsRecordData::A { address } => match Ipv4Addr::from_str(address) {
+            Ok(ip) => DnsRecordData::A {
+                address: ip.to_string(),
+            },
+            Err(_) => return Err(invalid(&record)),
+        },
+        DnsRecordData::Aaaa { address } => match Ipv6Addr::from_str(address) {
+            Ok(ip) => DnsRecordData::Aaaa {
+                address: ip.to_string(),
+            },
+            Err(_) => return Err(invalid(&record)),
+        },
+        DnsRecordData::Cname { target } => DnsRecordData::Cname {
+            target: normalize_host(target).ok_or_else(|| invalid(&record))?,
+        },
+        DnsRecordData::Mx {
+            preference,
+            exchange,
+        } => DnsRecordData::Mx {
+            preference: *preference,
+            exchange: normalize_host(exchange).ok_or_else(|| invalid(&record))?,
+        },
+        DnsRecordData::Txt { text } => {
+            if text.len() > MAX_TXT_LENGTH || text.chars().any(|c| c.is_control()) {
+                return Err(invalid(&record));
+            }
+            record.data.clone()
+        }
+    };
+    Ok(DnsRecord {
+        ttl: record.ttl,
+        data,
+    })
+}
+
+// host names are stored lowercased and without the root `.`
+fn normalize_host(host: &str) -> Option<String> {
+    let host = host.strip_suffix('.').unwrap_or(host).to_ascii_lowercase();
+    match validate_dns_name(&host) {
+        Ok(()) => Some(host),
+        Err(_) => None,
+    }
+}
+
+impl DnsRecordData {
+    pub fn record_type(&self) -> &'static str {
+        match self {
+            DnsRecordData::A { .. } => "A",
+            DnsRecordData::Aaaa { .. } => "AAAA",
+            DnsRecordData::Cname { .. } => "CNAME",
+            DnsRecordData::Txt { .. } => "TXT",
+            DnsRecordData::Mx { .. } => "MX",
+        }
+    }
+
+    /// rdata returns the record data as written in a zone file,
+    /// with fully qualified host names and quoted text
+    pub fn rdata(&self) -> String {
+        match self {
+            DnsRecordData::A { address } | DnsRecordData::Aaaa { address } => address.clone(),
+            DnsRecordData::Cname { target } => format!("{}.", target),
+            DnsRecordData::Mx {
+                preference,
+                exchange,
+            } => format!("{} {}.", preference, exchange),
+            DnsRecordData::Txt { text } => {
+                format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+        }
+    }
+
+    /// parse reads the record data of a record type from its zone file form
+    pub fn parse(record_type: &str, rdata: &str) -> Result<Self, StdError> {
+        let invalid = || StdError::parse_err("DnsRecordData", format!("invalid rdata {}", rdata));
+        let host = |host: &str| host.strip_suffix('.').unwrap_or(host).to_string();
+        let data = match record_type {
+            "A" => DnsRecordData::A {
+                address: rdata.to_string(),
+            },
+            "AAAA" => DnsRecordData::Aaaa {
+                address: rdata.to_string(),
+            },
+            "CNAME" => DnsRecordData::Cname {
+                target: host(rdata),
+            },
+            "MX" => {
+                let (preference, exchange) = rdata.split_once(' ').ok_or_else(invalid)?;
+                DnsRecordData::Mx {
+                    preference: preference.parse().map_err(|_| invalid())?,
+                    exchange: host(exchange),
+                }
+            }
+            "TXT" => {
+                let quoted = rdata
+                    .strip_prefix('"')
+                    .and_then(|text| text.strip_suffix('"'))
+                    .ok_or_else(invalid)?;
+                let mut text = String::new();
+                let mut chars = quoted.chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => text.push(chars.next().ok_or_else(invalid)?),
+                        c => text.push(c),
+                    }
+                }
+                DnsRecordData::Txt { text }
+            }
+            other => {
+                return Err(StdError::parse_err(
+                    "DnsRecordData",
+                    format!("unknown record type {}", other),
+                ))
+            }
+        };
+        Ok(data)
+    }
+}
+
+// `{ttl} {type} {rdata}`, e.g. `300 A 192.0.2.1`
+impl fmt::Display for DnsRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.ttl,
+            self.data.record_type(),
+            self.data.rdata()
+        )
+    }
+}
+
+impl FromStr for DnsRecord {
+    type Err = StdError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || StdError::parse_err("DnsRecord", format!("invalid record {}", s));
+        let mut parts = s.splitn(3, ' ');
+        let ttl = parts
+            .next()
+            .and_then(|ttl| ttl.parse().ok())
+            .ok_or_else(invalid)?;
+        let record_type = parts.next().ok_or_else(invalid)?;
+        let rdata = parts.next().ok_or_else(invalid)?;
+        Ok(DnsRecord {
+            ttl,
+            data: DnsRecordData::parse(record_type, rdata)?,
+        })
+    }
+}
@@ -18,8 +18,19 @@ pub enum ContractError {
     #[error("Name does not exist (name {name})")]
     NameNotExists { name: String },
 
+    #[error("Subdomain does not exist (name {name})")]
+    SubdomainNotFound { name: String },
+    #[error("Name token query failed (name {name} reason {reason})")]
+    Cw721QueryFailed { name: String, reason: String },
+    #[error("Base cost is not set")]
+    BaseCostNotSet {},
+    #[error("Withdrawal exceeds the protocol fees held (available {available})")]
+    InsufficientFees { available: Uint128 },
+
     #[error("Name has been taken (name {name})")]
     NameTaken { name: String },
+    #[error("Name is reserved (name {name})")]
+    NameReserved { name: String },
 
     #[error("Name too short (length {length} min_length {min_length})")]
     NameTooShort { length: u64, min_length: u64 },
@@ -28,6 +39,46 @@ pub enum ContractError {
     NameTooLong { length: u64, max_length: u64 },
     #[error("Name ownership is expired")]
     NameOwnershipExpired { name: String },
+    #[error("Name is registered for the maximum number of intervals (name {name})")]
+    MaxExpirationReached { name: String },
+    #[error("Name has too many labels (depth {depth} max_depth {max_depth})")]
+    NameTooDeep { depth: u64, max_depth: u64 },
+    #[error("Invalid naming rules")]
+    InvalidNamingRules {},
+    #[error("Invalid tld (tld {tld})")]
+    InvalidTld { tld: String },
+    #[error("Name is taken by a tld (name {name})")]
+    NameShadowsTld { name: String },
+    #[error("Tld does not exist (tld {tld})")]
+    TldNotExists { tld: String },
+    #[error("Tld is not open for registrations (tld {tld})")]
+    TldDisabled { tld: String },
+    #[error("Label is not DNS compatible (label {label})")]
+    NotDnsCompatible { label: String },
+    #[error("Domain is not in DNS mode (name {name})")]
+    DnsModeDisabled { name: String },
+    #[error("Invalid DNS record (record {record})")]
+    InvalidDnsRecord { record: String },
+    #[error("Too many DNS records (count {count} max {max})")]
+    TooManyDnsRecords { count: u64, max: u64 },
     #[error("Invalid character(char {c}")]
     InvalidCharacter { c: char },
+    #[error("Invalid internationalized name (name {name})")]
+    InvalidUnicodeName { name: String },
+    #[error("Name mixes scripts (label {label})")]
+    MixedScriptName { label: String },
+    #[error("Name is confusable with an ascii name (label {label})")]
+    ConfusableName { label: String },
+    #[error("Subdomains are not for sale (domain {domain})")]
+    SubdomainNotForSale { domain: String },
+    #[error("Subdomain fuse has been burned (name {name} fuses {fuses})")]
+    FuseBurned { name: String, fuses: u32 },
+    #[error("Name ownership is already in sync (name {name})")]
+    OwnershipInSync { name: String },
+    #[error("Name already has a pending dispute (name {name})")]
+    DisputePending { name: String },
+    #[error("Name has no pending dispute (name {name})")]
+    NoPendingDispute { name: String },
+    #[error("Dispute is timelocked (name {name} executable_at {executable_at})")]
+    DisputeTimelocked { name: String, executable_at: u64 },
 }
@@ -0,0 +1,1103 @@
+// Events emitted by the registry for indexers, one event per state change.
+// The registry state can be rebuilt by replaying them in order,
+// see EVENTS.md for the attributes of each event.
+//
+// Every event has a type below that converts into a cosmwasm Event and can be
+// parsed back with TryFrom, RegistryEvent parses any registry event.
+// Parsing accepts event types with or without the `wasm-` prefix added on chain.
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use cosmwasm_std::{Addr, Coin, Decimal, Event, StdError, StdResult, Uint128};
+
+use crate::state::{Config, DnsRecord, NamingRules, SubdomainSale, TldConfig};
+use crate::write_utils::DENOM;
+
+pub const REGISTER_EVENT: &str = "archid.register";
+pub const RENEW_EVENT: &str = "archid.renew";
+pub const RESOLVER_CHANGED_EVENT: &str = "archid.resolver_changed";
+pub const OWNER_CHANGED_EVENT: &str = "archid.owner_changed";
+pub const METADATA_UPDATED_EVENT: &str = "archid.metadata_updated";
+pub const SUBDOMAIN_REGISTERED_EVENT: &str = "archid.subdomain_registered";
+pub const SUBDOMAIN_EXTENDED_EVENT: &str = "archid.subdomain_extended";
+pub const SUBDOMAIN_REMOVED_EVENT: &str = "archid.subdomain_removed";
+pub const SUBDOMAIN_SALE_UPDATED_EVENT: &str = "archid.subdomain_sale_updated";
+pub const SUBDOMAIN_FUSES_BURNED_EVENT: &str = "archid.subdomain_fuses_burned";
+pub const RENEWAL_FUNDS_UPDATED_EVENT: &str = "archid.renewal_funds_updated";
+pub const NAME_REVOKED_EVENT: &str = "archid.name_revoked";
+pub const DISPUTE_SCHEDULED_EVENT: &str = "archid.dispute_scheduled";
+pub const DISPUTE_CANCELLED_EVENT: &str = "archid.dispute_cancelled";
+pub const CONFIG_UPDATED_EVENT: &str = "archid.config_updated";
+pub const TLD_UPDATED_EVENT: &str = "archid.tld_updated";
+pub const DNS_MODE_UPDATED_EVENT: &str = "archid.dns_mode_updated";
+pub const DNS_RECORDS_UPDATED_EVENT: &str = "archid.dns_records_updated";
+pub const NAME_PRUNED_EVENT: &str = "archid.name_pruned";
+pub const CLEANUP_POOL_UPDATED_EVENT: &str = "archid.cleanup_pool_updated";
+pub const NAME_RESERVED_EVENT: &str = "archid.name_reserved";
+pub const WITHDRAW_EVENT: &str = "archid.withdraw";
+
+const WASM_PREFIX: &str = "wasm-";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegisterEvent {
+    pub name: String,
+    pub owner: Addr,
+    pub resolver: Addr,
+    pub cost: Uint128,
+    pub created: u64,
+    pub expiration: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenewEvent {
+    pub name: String,
+    pub payer: Addr,
+    pub cost: Uint128,
+    pub expiration: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolverChangedEvent {
+    pub name: String,
+    pub resolver: Addr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnerChangedEvent {
+    pub name: String,
+    pub owner: Addr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetadataUpdatedEvent {
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubdomainRegisteredEvent {
+    pub name: String,
+    pub domain: String,
+    pub subdomain: String,
+    pub owner: Addr,
+    pub resolver: Addr,
+    pub created: u64,
+    pub expiration: u64,
+    // only set when the subdomain was bought through a subdomain sale
+    pub price: Option<Coin>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubdomainExtendedEvent {
+    pub name: String,
+    pub domain: String,
+    pub subdomain: String,
+    pub expiration: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubdomainRemovedEvent {
+    pub name: String,
+    pub domain: String,
+    pub subdomain: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubdomainSaleUpdatedEvent {
+    pub domain: String,
+    // None when the domain's subdomains were taken off sale
+    pub sale: Option<SubdomainSale>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubdomainFusesBurnedEvent {
+    pub name: String,
+    // every fuse burned on the subdomain so far
+    pub fuses: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenewalFundsUpdatedEvent {
+    pub name: String,
+    pub balance: Uint128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameRevokedEvent {
+    pub name: String,
+    pub reason: String,
+    pub previous_owner: Addr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisputeScheduledEvent {
+    pub name: String,
+    // `revoke` or `reassign`
+    pub dispute: String,
+    pub executable_at: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisputeCancelledEvent {
+    pub name: String,
+    pub dispute: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigUpdatedEvent {
+    pub config: Config,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TldUpdatedEvent {
+    pub tld: String,
+    pub config: TldConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DnsModeUpdatedEvent {
+    pub name: String,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DnsRecordsUpdatedEvent {
+    pub name: String,
+    pub records: Vec<DnsRecord>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NamePrunedEvent {
+    pub name: String,
+    pub previous_owner: Addr,
+    pub pruner: Addr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CleanupPoolUpdatedEvent {
+    pub balance: Uint128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameReservedEvent {
+    pub name: String,
+    pub reserved: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawEvent {
+    pub recipient: Addr,
+    pub amount: Uint128,
+    pub denom: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegistryEvent {
+    Register(RegisterEvent),
+    Renew(RenewEvent),
+    ResolverChanged(ResolverChangedEvent),
+    OwnerChanged(OwnerChangedEvent),
+    MetadataUpdated(MetadataUpdatedEvent),
+    SubdomainRegistered(SubdomainRegisteredEvent),
+    SubdomainExtended(SubdomainExtendedEvent),
+    SubdomainRemoved(SubdomainRemovedEvent),
+    SubdomainSaleUpdated(SubdomainSaleUpdatedEvent),
+    SubdomainFusesBurned(SubdomainFusesBurnedEvent),
+    RenewalFundsUpdated(RenewalFundsUpdatedEvent),
+    NameRevoked(NameRevokedEvent),
+    DisputeScheduled(DisputeScheduledEvent),
+    DisputeCancelled(DisputeCancelledEvent),
+    ConfigUpdated(ConfigUpdatedEvent),
+    TldUpdated(TldUpdatedEvent),
+    DnsModeUpdated(DnsModeUpdatedEvent),
+    DnsRecordsUpdated(DnsRecordsUpdatedEvent),
+    NamePruned(NamePrunedEvent),
+    CleanupPoolUpdated(CleanupPoolUpdatedEvent),
+    NameReserved(NameReservedEvent),
+    Withdraw(WithdrawEvent),
+}
+
+impl From<RegisterEvent> for Event {
+    fn from(e: RegisterEvent) -> Self {
+        Event::new(REGISTER_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("owner", e.owner)
+            .add_attribute("resolver", e.resolver)
+            .add_attribute("cost", e.cost)
+            .add_attribute("created", e.created.to_string())
+            .add_attribute("expiration", e.expiration.to_string())
+    }
+}
+
+impl TryFrom<&Event> for RegisterEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, REGISTER_EVENT)?;
+        Ok(RegisterEvent {
+            name: attribute(event, "name")?,
+            owner: addr_attribute(event, "owner")?,
+            resolver: addr_attribute(event, "resolver")?,
+            cost: parse_attribute(event, "cost")?,
+            created: parse_attribute(event, "created")?,
+            expiration: parse_attribute(event, "expiration")?,
+        })
+    }
+}
+
+impl From<RenewEvent> for Event {
+    fn from(e: RenewEvent) -> Self {
+        Event::new(RENEW_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("payer", e.payer)
+            .add_attribute("cost", e.cost)
+            .add_attribute("expiration", e.expiration.to_string())
+    }
+}
+
+impl TryFrom<&Event> for RenewEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, RENEW_EVENT)?;
+        Ok(RenewEvent {
+            name: attribute(event, "name")?,
+            payer: addr_attribute(event, "payer")?,
+            cost: parse_attribute(event, "cost")?,
+            expiration: parse_attribute(event, "expiration")?,
+        })
+    }
+}
+
+impl From<ResolverChangedEvent> for Event {
+    fn from(e: ResolverChangedEvent) -> Self {
+        Event::new(RESOLVER_CHANGED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("resolver", e.resolver)
+    }
+}
+
+impl TryFrom<&Event> for ResolverChangedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, RESOLVER_CHANGED_EVENT)?;
+        Ok(ResolverChangedEvent {
+            name: attribute(event, "name")?,
+            resolver: addr_attribute(event, "resolver")?,
+        })
+    }
+}
+
+impl From<OwnerChangedEvent> for Event {
+    fn from(e: OwnerChangedEvent) -> Self {
+        Event::new(OWNER_CHANGED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("owner", e.owner)
+    }
+}
+
+impl TryFrom<&Event> for OwnerChangedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, OWNER_CHANGED_EVENT)?;
+        Ok(OwnerChangedEvent {
+            name: attribute(event, "name")?,
+            owner: addr_attribute(event, "owner")?,
+        })
+    }
+}
+
+impl From<MetadataUpdatedEvent> for Event {
+    fn from(e: MetadataUpdatedEvent) -> Self {
+        Event::new(METADATA_UPDATED_EVENT).add_attribute("name", e.name)
+    }
+}
+
+impl TryFrom<&Event> for MetadataUpdatedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, METADATA_UPDATED_EVENT)?;
+        Ok(MetadataUpdatedEvent {
+            name: attribute(event, "name")?,
+        })
+    }
+}
+
+impl From<SubdomainRegisteredEvent> for Event {
+    fn from(e: SubdomainRegisteredEvent) -> Self {
+        let event = Event::new(SUBDOMAIN_REGISTERED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("domain", e.domain)
+            .add_attribute("subdomain", e.subdomain)
+            .add_attribute("owner", e.owner)
+            .add_attribute("resolver", e.resolver)
+            .add_attribute("created", e.created.to_string())
+            .add_attribute("expiration", e.expiration.to_string());
+        match e.price {
+            Some(price) => event
+                .add_attribute("price", price.amount)
+                .add_attribute("denom", price.denom),
+            None => event,
+        }
+    }
+}
+
+impl TryFrom<&Event> for SubdomainRegisteredEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, SUBDOMAIN_REGISTERED_EVENT)?;
+        let price = match find_attribute(event, "price") {
+            Some(_) => Some(Coin {
+                denom: attribute(event, "denom")?,
+                amount: parse_attribute(event, "price")?,
+            }),
+            None => None,
+        };
+        Ok(SubdomainRegisteredEvent {
+            name: attribute(event, "name")?,
+            domain: attribute(event, "domain")?,
+            subdomain: attribute(event, "subdomain")?,
+            owner: addr_attribute(event, "owner")?,
+            resolver: addr_attribute(event, "resolver")?,
+            created: parse_attribute(event, "created")?,
+            expiration: parse_attribute(event, "expiration")?,
+            price,
+        })
+    }
+}
+
+impl From<SubdomainExtendedEvent> for Event {
+    fn from(e: SubdomainExtendedEvent) -> Self {
+        Event::new(SUBDOMAIN_EXTENDED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("domain", e.domain)
+            .add_attribute("subdomain", e.subdomain)
+            .add_attribute("expiration", e.expiration.to_string())
+    }
+}
+
+impl TryFrom<&Event> for SubdomainExtendedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, SUBDOMAIN_EXTENDED_EVENT)?;
+        Ok(SubdomainExtendedEvent {
+            name: attribute(event, "name")?,
+            domain: attribute(event, "domain")?,
+            subdomain: attribute(event, "subdomain")?,
+            expiration: parse_attribute(event, "expiration")?,
+        })
+    }
+}
+
+impl From<SubdomainRemovedEvent> for Event {
+    fn from(e: SubdomainRemovedEvent) -> Self {
+        Event::new(SUBDOMAIN_REMOVED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("domain", e.domain)
+            .add_attribute("subdomain", e.subdomain)
+    }
+}
+
+impl TryFrom<&Event> for SubdomainRemovedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, SUBDOMAIN_REMOVED_EVENT)?;
+        Ok(SubdomainRemovedEvent {
+            name: attribute(event, "name")?,
+            domain: attribute(event, "domain")?,
+            subdomain: attribute(event, "subdomain")?,
+        })
+    }
+}
+
+impl From<SubdomainSaleUpdatedEvent> for Event {
+    fn from(e: SubdomainSaleUpdatedEvent) -> Self {
+        let event = Event::new(SUBDOMAIN_SALE_UPDATED_EVENT)
+            .add_attribute("domain", e.domain)
+            .add_attribute("for_sale", e.sale.is_some().to_string());
+        match e.sale {
+            Some(sale) => event
+                .add_attribute("price", sale.price)
+                .add_attribute("denom", sale.denom)
+                .add_attribute("max_duration", sale.max_duration.to_string()),
+            None => event,
+        }
+    }
+}
+
+impl TryFrom<&Event> for SubdomainSaleUpdatedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, SUBDOMAIN_SALE_UPDATED_EVENT)?;
+        let for_sale: bool = parse_attribute(event, "for_sale")?;
+        let sale = match for_sale {
+            true => Some(SubdomainSale {
+                price: parse_attribute(event, "price")?,
+                denom: attribute(event, "denom")?,
+                max_duration: parse_attribute(event, "max_duration")?,
+            }),
+            false => None,
+        };
+        Ok(SubdomainSaleUpdatedEvent {
+            domain: attribute(event, "domain")?,
+            sale,
+        })
+    }
+}
+
+impl From<SubdomainFusesBurnedEvent> for Event {
+    fn from(e: SubdomainFusesBurnedEvent) -> Self {
+        Event::new(SUBDOMAIN_FUSES_BURNED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("fuses", e.fuses.to_string())
+    }
+}
+
+impl TryFrom<&Event> for SubdomainFusesBurnedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, SUBDOMAIN_FUSES_BURNED_EVENT)?;
+        Ok(SubdomainFusesBurnedEvent {
+            name: attribute(event, "name")?,
+            fuses: parse_attribute(event, "fuses")?,
+        })
+    }
+}
+
+impl From<RenewalFundsUpdatedEvent> for Event {
+    fn from(e: RenewalFundsUpdatedEvent) -> Self {
+        Event::new(RENEWAL_FUNDS_UPDATED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("balance", e.balance)
+    }
+}
+
+impl TryFrom<&Event> for RenewalFundsUpdatedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, RENEWAL_FUNDS_UPDATED_EVENT)?;
+        Ok(RenewalFundsUpdatedEvent {
+            name: attribute(event, "name")?,
+            balance: parse_attribute(event, "balance")?,
+        })
+    }
+}
+
+impl From<NameRevokedEvent> for Event {
+    fn from(e: NameRevokedEvent) -> Self {
+        Event::new(NAME_REVOKED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("reason", e.reason)
+            .add_attribute("previous_owner", e.previous_owner)
+    }
+}
+
+impl TryFrom<&Event> for NameRevokedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, NAME_REVOKED_EVENT)?;
+        Ok(NameRevokedEvent {
+            name: attribute(event, "name")?,
+            reason: attribute(event, "reason")?,
+            previous_owner: addr_attribute(event, "previous_owner")?,
+        })
+    }
+}
+
+impl From<DisputeScheduledEvent> for Event {
+    fn from(e: DisputeScheduledEvent) -> Self {
+        Event::new(DISPUTE_SCHEDULED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("dispute", e.dispute)
+            .add_attribute("executable_at", e.executable_at.to_string())
+    }
+}
+
+impl TryFrom<&Event> for DisputeScheduledEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, DISPUTE_SCHEDULED_EVENT)?;
+        Ok(DisputeScheduledEvent {
+            name: attribute(event, "name")?,
+            dispute: attribute(event, "dispute")?,
+            executable_at: parse_attribute(event, "executable_at")?,
+        })
+    }
+}
+
+impl From<DisputeCancelledEvent> for Event {
+    fn from(e: DisputeCancelledEvent) -> Self {
+        Event::new(DISPUTE_CANCELLED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("dispute", e.dispute)
+    }
+}
+
+impl TryFrom<&Event> for DisputeCancelledEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, DISPUTE_CANCELLED_EVENT)?;
+        Ok(DisputeCancelledEvent {
+            name: attribute(event, "name")?,
+            dispute: attribute(event, "dispute")?,
+        })
+    }
+}
+
+impl From<ConfigUpdatedEvent> for Event {
+    fn from(e: ConfigUpdatedEvent) -> Self {
+        let c = e.config;
+        let event = Event::new(CONFIG_UPDATED_EVENT)
+            .add_attribute("admin", c.admin)
+            .add_attribute("wallet", c.wallet)
+            .add_attribute("cw721", c.cw721)
+            .add_attribute("base_cost", c.base_cost)
+            .add_attribute("base_expiration", c.base_expiration.to_string())
+            .add_attribute("subdomain_fee", c.subdomain_fee.to_string())
+            .add_attribute(
+                "allow_third_party_renewals",
+                c.allow_third_party_renewals.to_string(),
+            )
+            .add_attribute("keeper_fee", c.keeper_fee)
+            .add_attribute("renewal_window", c.renewal_window.to_string())
+            .add_attribute("grace_period", c.grace_period.to_string())
+            .add_attribute("dispute_timelock", c.dispute_timelock.to_string())
+            .add_attribute("cleanup_bounty", c.cleanup_bounty);
+        let event = add_rules_attributes(event, "name", &c.name_rules);
+        add_rules_attributes(event, "subdomain", &c.subdomain_rules)
+    }
+}
+
+fn add_rules_attributes(event: Event, prefix: &str, rules: &NamingRules) -> Event {
+    event
+        .add_attribute(
+            format!("{}_min_length", prefix),
+            rules.min_length.to_string(),
+        )
+        .add_attribute(
+            format!("{}_max_length", prefix),
+            rules.max_length.to_string(),
+        )
+        .add_attribute(
+            format!("{}_allow_hyphens", prefix),
+            rules.allow_hyphens.to_string(),
+        )
+        .add_attribute(
+            format!("{}_allow_underscores", prefix),
+            rules.allow_underscores.to_string(),
+        )
+        .add_attribute(
+            format!("{}_allow_unicode", prefix),
+            rules.allow_unicode.to_string(),
+        )
+        .add_attribute(
+            format!("{}_max_intervals", prefix),
+            rules.max_intervals.to_string(),
+        )
+}
+
+fn parse_rules_attributes(event: &Event, prefix: &str) -> StdResult<NamingRules> {
+    let key = |name: &str| format!("{}_{}", prefix, name);
+    Ok(NamingRules {
+        min_length: parse_attribute(event, &key("min_length"))?,
+        max_length: parse_attribute(event, &key("max_length"))?,
+        allow_hyphens: parse_attribute(event, &key("allow_hyphens"))?,
+        allow_underscores: parse_attribute(event, &key("allow_underscores"))?,
+        allow_unicode: parse_attribute(event, &key("allow_unicode"))?,
+        max_intervals: parse_attribute(event, &key("max_intervals"))?,
+    })
+}
+
+impl TryFrom<&Event> for ConfigUpdatedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, CONFIG_UPDATED_EVENT)?;
+        let subdomain_fee: Decimal = parse_attribute(event, "subdomain_fee")?;
+        Ok(ConfigUpdatedEvent {
+            config: Config {
+                admin: addr_attribute(event, "admin")?,
+                wallet: addr_attribute(event, "wallet")?,
+                cw721: addr_attribute(event, "cw721")?,
+                base_cost: parse_attribute(event, "base_cost")?,
+                base_expiration: parse_attribute(event, "base_expiration")?,
+                subdomain_fee,
+                allow_third_party_renewals: parse_attribute(event, "allow_third_party_renewals")?,
+                keeper_fee: parse_attribute(event, "keeper_fee")?,
+                renewal_window: parse_attribute(event, "renewal_window")?,
+                grace_period: parse_attribute(event, "grace_period")?,
+                dispute_timelock: parse_attribute(event, "dispute_timelock")?,
+                name_rules: parse_rules_attributes(event, "name")?,
+                subdomain_rules: parse_rules_attributes(event, "subdomain")?,
+                cleanup_bounty: parse_attribute(event, "cleanup_bounty")?,
+            },
+        })
+    }
+}
+
+impl From<TldUpdatedEvent> for Event {
+    fn from(e: TldUpdatedEvent) -> Self {
+        let t = e.config;
+        let event = Event::new(TLD_UPDATED_EVENT)
+            .add_attribute("tld", e.tld)
+            .add_attribute("cw721", t.cw721)
+            .add_attribute("base_cost", t.base_cost)
+            .add_attribute("base_expiration", t.base_expiration.to_string())
+            .add_attribute("enabled", t.enabled.to_string());
+        let event = add_rules_attributes(event, "name", &t.name_rules);
+        add_rules_attributes(event, "subdomain", &t.subdomain_rules)
+    }
+}
+
+impl TryFrom<&Event> for TldUpdatedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, TLD_UPDATED_EVENT)?;
+        Ok(TldUpdatedEvent {
+            tld: attribute(event, "tld")?,
+            config: TldConfig {
+                cw721: addr_attribute(event, "cw721")?,
+                base_cost: parse_attribute(event, "base_cost")?,
+                base_expiration: parse_attribute(event, "base_expiration")?,
+                name_rules: parse_rules_attributes(event, "name")?,
+                subdomain_rules: parse_rules_attributes(event, "subdomain")?,
+                enabled: parse_attribute(event, "enabled")?,
+            },
+        })
+    }
+}
+
+impl From<DnsModeUpdatedEvent> for Event {
+    fn from(e: DnsModeUpdatedEvent) -> Self {
+        Event::new(DNS_MODE_UPDATED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("enabled", e.enabled.to_string())
+    }
+}
+
+impl TryFrom<&Event> for DnsModeUpdatedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, DNS_MODE_UPDATED_EVENT)?;
+        Ok(DnsModeUpdatedEvent {
+            name: attribute(event, "name")?,
+            enabled: parse_attribute(event, "enabled")?,
+        })
+    }
+}
+
+// one `record` attribute per record, in zone file form
+impl From<DnsRecordsUpdatedEvent> for Event {
+    fn from(e: DnsRecordsUpdatedEvent) -> Self {
+        let event = Event::new(DNS_RECORDS_UPDATED_EVENT).add_attribute("name", e.name);
+        e.records.iter().fold(event, |event, record| {
+            event.add_attribute("record", record.to_string())
+        })
+    }
+}
+
+impl TryFrom<&Event> for DnsRecordsUpdatedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, DNS_RECORDS_UPDATED_EVENT)?;
+        Ok(DnsRecordsUpdatedEvent {
+            name: attribute(event, "name")?,
+            records: event
+                .attributes
+                .iter()
+                .filter(|attr| attr.key == "record")
+                .map(|attr| attr.value.parse())
+                .collect::<StdResult<Vec<DnsRecord>>>()?,
+        })
+    }
+}
+
+impl From<NamePrunedEvent> for Event {
+    fn from(e: NamePrunedEvent) -> Self {
+        Event::new(NAME_PRUNED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("previous_owner", e.previous_owner)
+            .add_attribute("pruner", e.pruner)
+    }
+}
+
+impl TryFrom<&Event> for NamePrunedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, NAME_PRUNED_EVENT)?;
+        Ok(NamePrunedEvent {
+            name: attribute(event, "name")?,
+            previous_owner: addr_attribute(event, "previous_owner")?,
+            pruner: addr_attribute(event, "pruner")?,
+        })
+    }
+}
+
+impl From<CleanupPoolUpdatedEvent> for Event {
+    fn from(e: CleanupPoolUpdatedEvent) -> Self {
+        Event::new(CLEANUP_POOL_UPDATED_EVENT).add_attribute("balance", e.balance)
+    }
+}
+
+impl TryFrom<&Event> for CleanupPoolUpdatedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, CLEANUP_POOL_UPDATED_EVENT)?;
+        Ok(CleanupPoolUpdatedEvent {
+            balance: parse_attribute(event, "balance")?,
+        })
+    }
+}
+
+impl From<NameReservedEvent> for Event {
+    fn from(e: NameReservedEvent) -> Self {
+        Event::new(NAME_RESERVED_EVENT)
+            .add_attribute("name", e.name)
+            .add_attribute("reserved", e.reserved.to_string())
+    }
+}
+
+impl TryFrom<&Event> for NameReservedEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, NAME_RESERVED_EVENT)?;
+        Ok(NameReservedEvent {
+            name: attribute(event, "name")?,
+            reserved: parse_attribute(event, "reserved")?,
+        })
+    }
+}
+
+impl From<WithdrawEvent> for Event {
+    fn from(e: WithdrawEvent) -> Self {
+        Event::new(WITHDRAW_EVENT)
+            .add_attribute("recipient", e.recipient)
+            .add_attribute("amount", e.amount)
+            .add_attribute("denom", e.denom)
+    }
+}
+
+impl TryFrom<&Event> for WithdrawEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        expect_type(event, WITHDRAW_EVENT)?;
+        Ok(WithdrawEvent {
+            recipient: addr_attribute(event, "recipient")?,
+            amount: parse_attribute(event, "amount")?,
+            // withdrawals were only made in DENOM before the attribute existed
+            denom: find_attribute(event, "denom").unwrap_or(DENOM).to_string(),
+        })
+    }
+}
+
+impl From<RegistryEvent> for Event {
+    fn from(e: RegistryEvent) -> Self {
+        match e {
+            RegistryEvent::Register(e) => e.into(),
+            RegistryEvent::Renew(e) => e.into(),
+            RegistryEvent::ResolverChanged(e) => e.into(),
+            RegistryEvent::OwnerChanged(e) => e.into(),
+            RegistryEvent::MetadataUpdated(e) => e.into(),
+            RegistryEvent::SubdomainRegistered(e) => e.into(),
+            RegistryEvent::SubdomainExtended(e) => e.into(),
+            RegistryEvent::SubdomainRemoved(e) => e.into(),
+            RegistryEvent::SubdomainSaleUpdated(e) => e.into(),
+            RegistryEvent::SubdomainFusesBurned(e) => e.into(),
+            RegistryEvent::RenewalFundsUpdated(e) => e.into(),
+            RegistryEvent::NameRevoked(e) => e.into(),
+            RegistryEvent::DisputeScheduled(e) => e.into(),
+            RegistryEvent::DisputeCancelled(e) => e.into(),
+            RegistryEvent::ConfigUpdated(e) => e.into(),
+            RegistryEvent::TldUpdated(e) => e.into(),
+            RegistryEvent::DnsModeUpdated(e) => e.into(),
+            RegistryEvent::DnsRecordsUpdated(e) => e.into(),
+            RegistryEvent::NamePruned(e) => e.into(),
+            RegistryEvent::CleanupPoolUpdated(e) => e.into(),
+            RegistryEvent::NameReserved(e) => e.into(),
+            RegistryEvent::Withdraw(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<&Event> for RegistryEvent {
+    type Error = StdError;
+    fn try_from(event: &Event) -> StdResult<Self> {
+        let parsed = match event_type(event) {
+            REGISTER_EVENT => RegistryEvent::Register(event.try_into()?),
+            RENEW_EVENT => RegistryEvent::Renew(event.try_into()?),
+            RESOLVER_CHANGED_EVENT => RegistryEvent::ResolverChanged(event.try_into()?),
+            OWNER_CHANGED_EVENT => RegistryEvent::OwnerChanged(event.try_into()?),
+            METADATA_UPDATED_EVENT => RegistryEvent::MetadataUpdated(event.try_into()?),
+            SUBDOMAIN_REGISTERED_EVENT => RegistryEvent::SubdomainRegistered(event.try_into()?),
+            SUBDOMAIN_EXTENDED_EVENT => RegistryEvent::SubdomainExtended(event.try_into()?),
+            SUBDOMAIN_REMOVED_EVENT => RegistryEvent::SubdomainRemoved(event.try_into()?),
+            SUBDOMAIN_SALE_UPDATED_EVENT => RegistryEvent::SubdomainSaleUpdated(event.try_into()?),
+            SUBDOMAIN_FUSES_BURNED_EVENT => RegistryEvent::SubdomainFusesBurned(event.try_into()?),
+            RENEWAL_FUNDS_UPDATED_EVENT => RegistryEvent::RenewalFundsUpdated(event.try_into()?),
+            NAME_REVOKED_EVENT => RegistryEvent::NameRevoked(event.try_into()?),
+            DISPUTE_SCHEDULED_EVENT => RegistryEvent::DisputeScheduled(event.try_into()?),
+            DISPUTE_CANCELLED_EVENT => RegistryEvent::DisputeCancelled(event.try_into()?),
+            CONFIG_UPDATED_EVENT => RegistryEvent::ConfigUpdated(event.try_into()?),
+            TLD_UPDATED_EVENT => RegistryEvent::TldUpdated(event.try_into()?),
+            DNS_MODE_UPDATED_EVENT => RegistryEvent::DnsModeUpdated(event.try_into()?),
+            DNS_RECORDS_UPDATED_EVENT => RegistryEvent::DnsRecordsUpdated(event.try_into()?),
+            NAME_PRUNED_EVENT => RegistryEvent::NamePruned(event.try_into()?),
+            CLEANUP_POOL_UPDATED_EVENT => RegistryEvent::CleanupPoolUpdated(event.try_into()?),
+            NAME_RESERVED_EVENT => RegistryEvent::NameReserved(event.try_into()?),
+            WITHDRAW_EVENT => RegistryEvent::Withdraw(event.try_into()?),
+            other => {
+                return Err(StdError::parse_err(
+                    "RegistryEvent",
+                    format!("unknown event type {}", other),
+                ))
+            }
+        };
+        Ok(parsed)
+    }
+}
+
+// event type without the `wasm-` prefix
+fn event_type(event: &Event) -> &str {
+    event.ty.strip_prefix(WASM_PREFIX).unwrap_or(&event.ty)
+}
+
+fn expect_type(event: &Event, ty: &str) -> StdResult<()> {
+    match event_type(event) == ty {
+        true => Ok(()),
+        false => Err(StdError::parse_err(
+            ty,
+            format!("unexpected event type {}", event.ty),
+        )),
+    }
+}
+
+fn find_attribute<'a>(event: &'a Event, key: &str) -> Option<&'a str> {
+    event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.as_str())
+}
+
+fn attribute(event: &Event, key: &str) -> StdResult<String> {
+    match find_attribute(event, key) {
+        Some(value) => Ok(value.to_string()),
+        None => Err(StdError::parse_err(
+            event.ty.as_str(),
+            format!("missing attribute {}", key),
+        )),
+    }
+}
+
+fn addr_attribute(event: &Event, key: &str) -> StdResult<Addr> {
+    Ok(Addr::unchecked(attribute(event, key)?))
+}
+
+fn parse_attribute<T: FromStr>(event: &Event, key: &str) -> StdResult<T> {
+    attribute(event, key)?
+        .parse::<T>()
+        .map_err(|_| StdError::parse_err(event.ty.as_str(), format!("invalid attribute {}", key)))
+}
+
+pub fn register_event(
+    name: &str,
+    owner: &Addr,
+    cost: Uint128,
+    created: u64,
+    expiration: u64,
+) -> Event {
+    RegisterEvent {
+        name: name.to_string(),
+        owner: owner.clone(),
+        resolver: owner.clone(),
+        cost,
+        created,
+        expiration,
+    }
+    .into()
+}
+
+pub fn renew_event(name: &str, payer: &Addr, cost: Uint128, expiration: u64) -> Event {
+    RenewEvent {
+        name: name.to_string(),
+        payer: payer.clone(),
+        cost,
+        expiration,
+    }
+    .into()
+}
+
+pub fn resolver_changed_event(name: &str, resolver: &Addr) -> Event {
+    ResolverChangedEvent {
+        name: name.to_string(),
+        resolver: resolver.clone(),
+    }
+    .into()
+}
+
+pub fn owner_changed_event(name: &str, owner: &Addr) -> Event {
+    OwnerChangedEvent {
+        name: name.to_string(),
+        owner: owner.clone(),
+    }
+    .into()
+}
+
+pub fn metadata_updated_event(name: &str) -> Event {
+    MetadataUpdatedEvent {
+        name: name.to_string(),
+    }
+    .into()
+}
+
+// price is only set when the subdomain was bought through a subdomain sale
+#[allow(clippy::too_many_arguments)]
+pub fn subdomain_registered_event(
+    domain: &str,
+    subdomain: &str,
+    owner: &Addr,
+    resolver: &Addr,
+    created: u64,
+    expiration: u64,
+    price: Option<(&str, Uint128)>,
+) -> Event {
+    SubdomainRegisteredEvent {
+        name: format!("{}.{}", subdomain, domain),
+        domain: domain.to_string(),
+        subdomain: subdomain.to_string(),
+        owner: owner.clone(),
+        resolver: resolver.clone(),
+        created,
+        expiration,
+        price: price.map(|(denom, amount)| Coin {
+            denom: denom.to_string(),
+            amount,
+        }),
+    }
+    .into()
+}
+
+pub fn subdomain_extended_event(domain: &str, subdomain: &str, expiration: u64) -> Event {
+    SubdomainExtendedEvent {
+        name: format!("{}.{}", subdomain, domain),
+        domain: domain.to_string(),
+        subdomain: subdomain.to_string(),
+        expiration,
+    }
+    .into()
+}
+
+pub fn subdomain_removed_event(domain: &str, subdomain: &str) -> Event {
+    SubdomainRemovedEvent {
+        name: format!("{}.{}", subdomain, domain),
+        domain: domain.to_string(),
+        subdomain: subdomain.to_string(),
+    }
+    .into()
+}
+
+// sale terms are left out when the domain's subdomains are taken off sale
+pub fn subdomain_sale_updated_event(domain: &str, sale: Option<&SubdomainSale>) -> Event {
+    SubdomainSaleUpdatedEvent {
+        domain: domain.to_string(),
+        sale: sale.cloned(),
+    }
+    .into()
+}
+
+// fuses holds every fuse burned on the subdomain so far
+pub fn subdomain_fuses_burned_event(name: &str, fuses: u32) -> Event {
+    SubdomainFusesBurnedEvent {
+        name: name.to_string(),
+        fuses,
+    }
+    .into()
+}
+
+pub fn renewal_funds_updated_event(name: &str, balance: Uint128) -> Event {
+    RenewalFundsUpdatedEvent {
+        name: name.to_string(),
+        balance,
+    }
+    .into()
+}
+
+pub fn name_revoked_event(name: &str, reason: &str, previous_owner: &str) -> Event {
+    NameRevokedEvent {
+        name: name.to_string(),
+        reason: reason.to_string(),
+        previous_owner: Addr::unchecked(previous_owner),
+    }
+    .into()
+}
+
+pub fn dispute_scheduled_event(name: &str, action: &str, executable_at: u64) -> Event {
+    DisputeScheduledEvent {
+        name: name.to_string(),
+        dispute: action.to_string(),
+        executable_at,
+    }
+    .into()
+}
+
+pub fn dispute_cancelled_event(name: &str, action: &str) -> Event {
+    DisputeCancelledEvent {
+        name: name.to_string(),
+        dispute: action.to_string(),
+    }
+    .into()
+}
+
+pub fn config_updated_event(c: &Config) -> Event {
+    ConfigUpdatedEvent { config: c.clone() }.into()
+}
+
+pub fn tld_updated_event(tld: &str, config: &TldConfig) -> Event {
+    TldUpdatedEvent {
+        tld: tld.to_string(),
+        config: config.clone(),
+    }
+    .into()
+}
+
+pub fn dns_mode_updated_event(name: &str, enabled: bool) -> Event {
+    DnsModeUpdatedEvent {
+        name: name.to_string(),
+        enabled,
+    }
+    .into()
+}
+
+pub fn dns_records_updated_event(name: &str, records: &[DnsRecord]) -> Event {
+    DnsRecordsUpdatedEvent {
+        name: name.to_string(),
+        records: records.to_vec(),
+    }
+    .into()
+}
+
+pub fn name_pruned_event(name: &str, previous_owner: &str, pruner: &Addr) -> Event {
+    NamePrunedEvent {
+        name: name.to_string(),
+        previous_owner: Addr::unchecked(previous_owner),
+        pruner: pruner.clone(),
+    }
+    .into()
+}
+
+pub fn cleanup_pool_updated_event(balance: Uint128) -> Event {
+    CleanupPoolUpdatedEvent { balance }.into()
+}
+
+pub fn name_reserved_event(name: &str, reserved: bool) -> Event {
+    NameReservedEvent {
+        name: name.to_string(),
+        reserved,
+    }
+    .into()
+}
+
+pub fn withdraw_event(recipient: &Addr, amount: Uint128, denom: &str) -> Event {
+    WithdrawEvent {
+        recipient: recipient.clone(),
+        amount,
+        denom: denom.to_string(),
+    }
+    .into()
+}
@@ -1,22 +1,50 @@
 use crate::error::ContractError;
+use crate::events::{
+    cleanup_pool_updated_event, config_updated_event, dispute_cancelled_event,
+    dispute_scheduled_event, dns_mode_updated_event, dns_records_updated_event,
+    metadata_updated_event, name_pruned_event, name_reserved_event, name_revoked_event,
+    owner_changed_event, register_event, renew_event, renewal_funds_updated_event,
+    resolver_changed_event, subdomain_extended_event, subdomain_fuses_burned_event,
+    subdomain_registered_event, subdomain_removed_event, subdomain_sale_updated_event,
+    tld_updated_event, withdraw_event,
+};
 
+use crate::dns::{normalize_records, validate_dns_name};
+use crate::idn::to_unicode;
 use crate::msg::MetaDataUpdateMsg;
+use crate::parser::TLD;
 use crate::read_utils::{
-    get_subdomain_prefix, is_expired, query_current_metadata, query_name_owner, validate_name,
-    validate_subdomain,
+    active_fuses, dispute_timelock, get_name_body, is_expired, load_record, name_config,
+    query_current_metadata, query_name_owner, query_owner_of, registration_config,
+    subdomain_records, validate_name, validate_subdomain, validate_top_level,
+};
+use crate::state::{
+    cleanup_pool, cleanup_pool_read, config, config_read, dns_mode, dns_mode_read, dns_records,
+    expiration_key, name_owner_read, parent_name, pending_dispute, pending_dispute_read,
+    remove_record, renewal_expiration_index_read, renewal_funds_read, renewal_funds_total_read,
+    reserved_names, reserved_names_read, resolver, resolver_read, save_name_owner, save_record,
+    save_renewal_funds, subdomain_fuses, subdomain_sale, subdomain_sale_read, timelock_decrease,
+    timelock_decrease_read, tlds, tlds_read, top_level_expiration_index,
+    top_level_expiration_index_read, Config, DisputeAction, DnsRecord, NameRecord, PendingDispute,
+    SubDomainStatus, SubdomainSale, TimelockDecrease, TldConfig, ALL_FUSES, CANNOT_REMOVE,
+    MAX_DISPUTE_TIMELOCK, MAX_LABEL_LENGTH, PARENT_CANNOT_CONTROL,
 };
-use crate::state::{config, config_read, resolver, Config, NameRecord, SubDomainStatus};
 use crate::write_utils::{
-    burn_handler, burn_remint_subdomain, mint_handler, register_new_subdomain,
-    remove_subdomain_metadata, send_data_update, send_tokens, update_metadata_expiry,
-    update_subdomain_expiry, update_subdomain_metadata, DENOM,
+    burn_handler, burn_remint_subdomain, burn_submsg, clear_owner_records, mint_handler,
+    reconcile_metadata, register_new_subdomain, remove_subdomain_metadata, send_data_update,
+    send_funds, send_tokens, update_metadata_expiry, update_subdomain_expiry,
+    update_subdomain_metadata, DENOM,
 };
 use archid_token::Metadata;
 
-use cosmwasm_std::{Addr, DepsMut, Env, MessageInfo, Response, Uint128};
+use cosmwasm_std::{
+    Addr, CosmosMsg, Decimal, DepsMut, Env, Event, MessageInfo, Order, Record, Response, StdError,
+    StdResult, SubMsg, Uint128, Uint64,
+};
 use cw_utils::must_pay;
 use std::convert::TryFrom;
-const MAX_BASE_INTERVAL: u64 = 3;
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
 
 pub fn execute_register(
     deps: DepsMut,
@@ -24,101 +52,529 @@ pub fn execute_register(
     info: MessageInfo,
     name: String,
 ) -> Result<Response, ContractError> {
-    validate_name(&name)?;
-    let key = &name.as_bytes();
-    let curr = resolver(deps.storage).may_load(key)?;
-    let c: Config = config_read(deps.storage).load()?;
+    let c: Config = registration_config(deps.storage, &name)?;
+    validate_name(&name, &c.name_rules)?;
     let res = must_pay(&info, &String::from(DENOM))?;
-    let mut messages = Vec::new();
-    let mut registration: u64 =
-        u64::try_from(((res.checked_div(c.base_cost)).unwrap()).u128()).unwrap();
+    let registration = registration_intervals(res, c.base_cost)?;
+    let (messages, events, _expiration) =
+        register_name(deps, &env, &info.sender, &c, &name, registration, res)?;
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_events(events)
+        .add_attribute("action", "register")
+        .add_attribute("domain", name))
+}
+
+pub fn execute_renew_registration(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    validate_top_level(&name)?;
+    let c: Config = name_config(deps.storage, &name)?;
+    let res = must_pay(&info, &String::from(DENOM))?;
+    let registration = registration_intervals(res, c.base_cost)?;
+    let (message, expiration) = renew_name(deps, &env, &info.sender, &c, &name, registration)?;
+
+    Ok(Response::new()
+        .add_message(message)
+        .add_event(renew_event(&name, &info.sender, res, expiration))
+        .add_attribute("action", "renew_registration")
+        .add_attribute("domain", name)
+        .add_attribute("payer", info.sender))
+}
+
+// batches are paid in one payment, covering the same number of
+// intervals for every name at the base_cost of its tld
+pub fn execute_batch_register(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    names: Vec<String>,
+) -> Result<Response, ContractError> {
+    validate_batch(&names, |name| {
+        validate_name(name, &registration_config(deps.storage, name)?.name_rules)
+    })?;
+    let configs = names
+        .iter()
+        .map(|name| registration_config(deps.storage, name))
+        .collect::<Result<Vec<Config>, ContractError>>()?;
+    let res = must_pay(&info, &String::from(DENOM))?;
+    let registration = registration_intervals(res, batch_cost(&configs)?)?;
+
+    let mut response = Response::new().add_attribute("action", "batch_register");
+    for (name, c) in names.into_iter().zip(configs) {
+        let cost = c.base_cost * Uint128::from(registration);
+        let (messages, events, expiration) = register_name(
+            deps.branch(),
+            &env,
+            &info.sender,
+            &c,
+            &name,
+            registration,
+            cost,
+        )?;
+        response = response
+            .add_messages(messages)
+            .add_events(events)
+            .add_attribute("domain", name)
+            .add_attribute("cost", cost)
+            .add_attribute("expiration", expiration.to_string());
+    }
+    Ok(response)
+}
+
+pub fn execute_batch_renew(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    names: Vec<String>,
+) -> Result<Response, ContractError> {
+    validate_batch(&names, validate_top_level)?;
+    let configs = names
+        .iter()
+        .map(|name| name_config(deps.storage, name))
+        .collect::<StdResult<Vec<Config>>>()?;
+    let res = must_pay(&info, &String::from(DENOM))?;
+    let registration = registration_intervals(res, batch_cost(&configs)?)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "batch_renew")
+        .add_attribute("payer", info.sender.clone());
+    for (name, c) in names.into_iter().zip(configs) {
+        let cost = c.base_cost * Uint128::from(registration);
+        let (message, expiration) =
+            renew_name(deps.branch(), &env, &info.sender, &c, &name, registration)?;
+        response = response
+            .add_message(message)
+            .add_event(renew_event(&name, &info.sender, cost, expiration))
+            .add_attribute("domain", name)
+            .add_attribute("cost", cost)
+            .add_attribute("expiration", expiration.to_string());
+    }
+    Ok(response)
+}
+
+// registration_intervals returns how many base intervals a payment covers
+fn registration_intervals(payment: Uint128, cost: Uint128) -> Result<u64, ContractError> {
+    if cost.is_zero() {
+        return Err(ContractError::BaseCostNotSet {});
+    }
+    let registration = u64::try_from((payment / cost).u128())
+        .map_err(|_| ContractError::InvalidPayment { amount: payment })?;
     if registration < 1 {
-        return Err(ContractError::InvalidPayment { amount: res });
+        return Err(ContractError::InvalidPayment { amount: payment });
     }
+    Ok(registration)
+}
+
+// interval_expiration returns the time a number of base intervals after `from`
+fn interval_expiration(c: &Config, intervals: u64, from: u64) -> Result<u64, ContractError> {
+    let expiration = Uint64::from(c.base_expiration)
+        .checked_mul(Uint64::from(intervals))
+        .and_then(|duration| duration.checked_add(Uint64::from(from)))
+        .map_err(StdError::from)?;
+    Ok(expiration.u64())
+}
+
+// batch_cost returns the price of one base interval for every name in a batch,
+// given the config of each name's tld
+fn batch_cost(configs: &[Config]) -> Result<Uint128, ContractError> {
+    let mut cost = Uint128::zero();
+    for c in configs {
+        cost = cost.checked_add(c.base_cost).map_err(StdError::from)?;
+    }
+    Ok(cost)
+}
+
+// validate_batch fails the whole batch on any invalid or repeated name
+fn validate_batch<F>(names: &[String], validate: F) -> Result<(), ContractError>
+where
+    F: Fn(&str) -> Result<(), ContractError>,
+{
+    if names.is_empty() {
+        return Err(ContractError::InvalidInput {});
+    }
+    for (i, name) in names.iter().enumerate() {
+        validate(name)?;
+        if names[..i].contains(name) {
+            return Err(ContractError::InvalidInput {});
+        }
+    }
+    Ok(())
+}
 
-    if registration > MAX_BASE_INTERVAL {
-        registration = MAX_BASE_INTERVAL;
+fn register_name(
+    deps: DepsMut,
+    env: &Env,
+    owner: &Addr,
+    c: &Config,
+    name: &String,
+    registration: u64,
+    cost: Uint128,
+) -> Result<(Vec<CosmosMsg>, Vec<Event>, u64), ContractError> {
+    let key = &name.as_bytes();
+    if *owner != c.admin && reserved_names_read(deps.storage).may_load(key)?.is_some() {
+        return Err(ContractError::NameReserved {
+            name: name.to_string(),
+        });
     }
+    let curr = resolver(deps.storage).may_load(key)?;
+    let mut messages = Vec::new();
+    let mut events = Vec::new();
+    let registration = match registration > c.name_rules.max_intervals {
+        true => c.name_rules.max_intervals,
+        false => registration,
+    };
     if let Some(curr_value) = curr {
-        if !curr_value.is_expired(&env.block) {
-            return Err(ContractError::NameTaken { name });
+        if !curr_value.is_expired(&env.block) || curr_value.in_grace(&env.block, c.grace_period) {
+            return Err(ContractError::NameTaken {
+                name: name.to_string(),
+            });
         } else {
-            let burn_msg = burn_handler(&name, &c.cw721)?;
+            // return unspent renewal funds to the expired name's owner
+            if let Some(balance) = renewal_funds_read(deps.storage).may_load(key)? {
+                let owner_response = query_name_owner(name, &c.cw721, &deps)?;
+                messages.push(send_tokens(
+                    &Addr::unchecked(owner_response.owner),
+                    balance,
+                )?);
+                save_renewal_funds(deps.storage, key, Uint128::zero())?;
+                events.push(renewal_funds_updated_event(name, Uint128::zero()));
+            }
+            let burn_msg = burn_handler(name, &c.cw721)?;
             messages.push(burn_msg);
         }
     }
-    let expiration: u64 =
-        c.base_expiration.checked_mul(registration).unwrap() + env.block.time.seconds();
+    let expiration: u64 = interval_expiration(c, registration, env.block.time.seconds())?;
     let created: u64 = env.block.time.seconds();
 
     let record = NameRecord {
-        resolver: info.sender.clone(),
+        resolver: owner.clone(),
         created,
         expiration,
     };
-    let mint_resp = mint_handler(&name, &info.sender, &c.cw721, created, expiration)?;
+    let mint_resp = mint_handler(name, owner, &c.cw721, created, expiration)?;
     messages.push(mint_resp);
-    resolver(deps.storage).save(key, &record)?;
-    Ok(Response::new()
-        .add_messages(messages)
-        .add_attribute("action", "register")
-        .add_attribute("domain", name))
+    save_record(deps.storage, key, &record)?;
+    save_name_owner(deps.storage, key, owner)?;
+    // subdomain sale terms, dns settings and disputes belong to the previous owner
+    subdomain_sale(deps.storage).remove(key);
+    dns_mode(deps.storage).remove(key);
+    dns_records(deps.storage).remove(key);
+    pending_dispute(deps.storage).remove(key);
+    events.push(register_event(name, owner, cost, created, expiration));
+    Ok((messages, events, expiration))
 }
 
-pub fn execute_renew_registration(
+// renew_name extends a name's lifetime, payers other than the owner
+// are only accepted if third party renewals are enabled
+fn renew_name(
     deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    name: String,
-) -> Result<Response, ContractError> {
-    validate_name(&name)?;
-    let key = &name.as_bytes();
-    if (resolver(deps.storage).may_load(key)?).is_none() {
-        return Err(ContractError::InvalidInput {});
+    env: &Env,
+    sender: &Addr,
+    c: &Config,
+    name: &String,
+    registration: u64,
+) -> Result<(CosmosMsg, u64), ContractError> {
+    let curr = load_record(deps.storage, name)?;
+    if curr.is_expired(&env.block) && !curr.in_grace(&env.block, c.grace_period) {
+        return Err(ContractError::NameOwnershipExpired {
+            name: name.to_string(),
+        });
     }
-    let curr = (resolver(deps.storage).may_load(key)?).unwrap();
+    if !c.allow_third_party_renewals {
+        let owner_response = query_name_owner(name, &c.cw721, &deps)?;
 
-    let c: Config = config_read(deps.storage).load()?;
-    if is_expired(&deps, key, &env.block) {
-        return Err(ContractError::NameOwnershipExpired { name });
-    }
-    let owner_response = query_name_owner(&name, &c.cw721, &deps).unwrap();
-
-    if owner_response.owner != info.sender {
-        return Err(ContractError::Unauthorized {});
+        if owner_response.owner != *sender {
+            return Err(ContractError::Unauthorized {});
+        }
     }
+    extend_registration(deps, env, c, name, curr, registration)
+}
 
-    let res = must_pay(&info, &String::from(DENOM))?;
-    let registration: u64 =
-        u64::try_from(((res.checked_div(c.base_cost)).unwrap()).u128()).unwrap();
-    if registration < 1 {
-        return Err(ContractError::InvalidPayment { amount: res });
-    }
+fn extend_registration(
+    deps: DepsMut,
+    env: &Env,
+    c: &Config,
+    name: &String,
+    curr: NameRecord,
+    registration: u64,
+) -> Result<(CosmosMsg, u64), ContractError> {
     let created: u64 = env.block.time.seconds();
-    let mut expiration: u64 =
-        c.base_expiration.checked_mul(registration).unwrap() + curr.expiration;
-    let max_expiration: u64 = c.base_expiration.checked_mul(MAX_BASE_INTERVAL).unwrap() + created;
+    let mut expiration: u64 = interval_expiration(c, registration, curr.expiration)?;
+    let max_expiration: u64 = interval_expiration(c, c.name_rules.max_intervals, created)?;
     if expiration > max_expiration {
         expiration = max_expiration;
     }
+    // a lowered max_intervals must not shorten existing registrations
+    if expiration <= curr.expiration {
+        return Err(ContractError::MaxExpirationReached {
+            name: name.to_string(),
+        });
+    }
 
     let record = NameRecord {
         resolver: curr.resolver,
         created,
         expiration,
     };
-    resolver(deps.storage).save(key, &record)?;
+    save_record(deps.storage, name.as_bytes(), &record)?;
+
+    let resp = update_metadata_expiry(deps, &c.cw721, name.clone(), expiration)?;
+    Ok((resp, expiration))
+}
 
-    let resp = update_metadata_expiry(deps, &c.cw721, name.clone(), expiration);
+pub fn execute_deposit_renewal_funds(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    validate_top_level(&name)?;
+    let c: Config = name_config(deps.storage, &name)?;
+    let key = name.as_bytes();
+    let curr = match resolver(deps.storage).may_load(key)? {
+        Some(record) => record,
+        None => return Err(ContractError::NameNotExists { name }),
+    };
+    if curr.is_expired(&env.block) {
+        return Err(ContractError::NameOwnershipExpired { name });
+    }
+    if !c.allow_third_party_renewals {
+        let owner_response = query_name_owner(&name, &c.cw721, &deps)?;
+        if owner_response.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+    let deposit = must_pay(&info, &String::from(DENOM))?;
+    let balance = renewal_funds_read(deps.storage)
+        .may_load(key)?
+        .unwrap_or_default()
+        .checked_add(deposit)
+        .map_err(StdError::from)?;
+    save_renewal_funds(deps.storage, key, balance)?;
 
     Ok(Response::new()
-        .add_messages(resp)
-        .add_attribute("action", "renew_registration")
-        .add_attribute("domain", name))
+        .add_event(renewal_funds_updated_event(&name, balance))
+        .add_attribute("action", "deposit_renewal_funds")
+        .add_attribute("domain", name)
+        .add_attribute("amount", deposit)
+        .add_attribute("balance", balance))
+}
+
+// escrowed funds can be withdrawn by the name's recorded owner, a transferred
+// name's funds are refunded to its previous owner once its ownership is synced
+pub fn execute_withdraw_renewal_funds(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let c: Config = name_config(deps.storage, &name)?;
+    let key = name.as_bytes();
+    let balance = renewal_funds_read(deps.storage)
+        .may_load(key)?
+        .unwrap_or_default();
+    if amount.is_zero() || amount > balance {
+        return Err(ContractError::InvalidInput {});
+    }
+    if escrow_owner(&deps, &name, &c.cw721)? != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let balance = balance - amount;
+    save_renewal_funds(deps.storage, key, balance)?;
+    let resp = send_tokens(&info.sender, amount)?;
+
+    Ok(Response::new()
+        .add_message(resp)
+        .add_event(renewal_funds_updated_event(&name, balance))
+        .add_attribute("action", "withdraw_renewal_funds")
+        .add_attribute("domain", name)
+        .add_attribute("amount", amount)
+        .add_attribute("balance", balance))
+}
+
+// renews names about to expire from their escrowed funds,
+// the caller earns the keeper fee for every name renewed
+pub fn execute_process_renewals(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let c: Config = config_read(deps.storage).load()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // unexpired names expiring within the renewal window
+    let mut start = (env.block.time.seconds() + 1).to_be_bytes().to_vec();
+    let end = (env.block.time.seconds() + c.renewal_window + 1).to_be_bytes();
+    if let Some(name) = start_after {
+        // a name renewed or pruned since has left the window, which starts over
+        if let Some(record) = resolver_read(deps.storage).may_load(name.as_bytes())? {
+            let mut after = expiration_key(record.expiration, name.as_bytes());
+            after.push(0);
+            if after > start && after[..] < end[..] {
+                start = after;
+            }
+        }
+    }
+
+    let mut due = Vec::new();
+    let mut last_scanned = None;
+    for item in renewal_expiration_index_read(deps.storage)
+        .range(Some(&start), Some(&end), Order::Ascending)
+        .take(limit)
+    {
+        let (_key, name) = item?;
+        last_scanned = Some(name.clone());
+        // names are renewed at the base_cost of their tld
+        let name_c = name_config(deps.storage, &name)?;
+        let renewal_cost = name_c
+            .base_cost
+            .checked_add(c.keeper_fee)
+            .map_err(StdError::from)?;
+        let balance = match renewal_funds_read(deps.storage).may_load(name.as_bytes())? {
+            Some(balance) if balance >= renewal_cost => balance,
+            _ => continue,
+        };
+        let record = resolver_read(deps.storage).load(name.as_bytes())?;
+        // names that can't be extended by a full interval without passing
+        // the max_intervals cap are left for their owner to renew
+        let max_expiration = interval_expiration(
+            &name_c,
+            name_c.name_rules.max_intervals,
+            env.block.time.seconds(),
+        )?;
+        if interval_expiration(&name_c, 1, record.expiration)? > max_expiration {
+            continue;
+        }
+        due.push((name, name_c, record, renewal_cost, balance - renewal_cost));
+    }
+
+    let mut keeper_fees = Uint128::zero();
+    let mut response = Response::new()
+        .add_attribute("action", "process_renewals")
+        .add_attribute("keeper", info.sender.clone());
+    for (name, name_c, record, cost, balance) in due {
+        let (message, expiration) =
+            extend_registration(deps.branch(), &env, &name_c, &name, record, 1)?;
+        save_renewal_funds(deps.storage, name.as_bytes(), balance)?;
+        keeper_fees += c.keeper_fee;
+        // auto renewals are paid from the name's escrow, keeper fee included
+        let payer = escrow_owner(&deps, &name, &name_c.cw721)?;
+        response = response
+            .add_message(message)
+            .add_event(renew_event(&name, &payer, cost, expiration))
+            .add_event(renewal_funds_updated_event(&name, balance))
+            .add_attribute("domain", name)
+            .add_attribute("expiration", expiration.to_string());
+    }
+    if !keeper_fees.is_zero() {
+        response = response.add_message(send_tokens(&info.sender, keeper_fees)?);
+    }
+    if let Some(name) = last_scanned {
+        response = response.add_attribute("last_scanned", name);
+    }
+    Ok(response.add_attribute("keeper_fee", keeper_fees))
+}
+// execute_fund_cleanup_pool adds the funds sent to the pool paying cleanup bounties
+pub fn execute_fund_cleanup_pool(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let deposit = must_pay(&info, &String::from(DENOM))?;
+    let balance = cleanup_pool_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default()
+        .checked_add(deposit)
+        .map_err(StdError::from)?;
+    cleanup_pool(deps.storage).save(&balance)?;
+    Ok(Response::new()
+        .add_event(cleanup_pool_updated_event(balance))
+        .add_attribute("action", "fund_cleanup_pool")
+        .add_attribute("amount", deposit))
+}
+
+// execute_prune_expired burns and removes top-level names whose grace period
+// has ended, together with their subdomains. The sender is paid the cleanup
+// bounty for each pruned name while the cleanup pool lasts.
+pub fn execute_prune_expired(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let c: Config = config_read(deps.storage).load()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // top-level names that expired at least grace_period ago,
+    // subdomains are pruned with their domain
+    let mut due = Vec::new();
+    if let Some(last) = env.block.time.seconds().checked_sub(c.grace_period) {
+        let end = (last + 1).to_be_bytes();
+        for item in top_level_expiration_index_read(deps.storage)
+            .range(None, Some(&end), Order::Ascending)
+            .take(limit)
+        {
+            due.push(item?);
+        }
+    }
+
+    let mut response = Response::new()
+        .add_attribute("action", "prune_expired")
+        .add_attribute("pruner", info.sender.clone());
+    let mut pruned: u128 = 0;
+    for (index_key, name) in due.iter() {
+        let name_c = name_config(deps.storage, name)?;
+        // tokens burned on the cw721 contract directly fall back to their last
+        // recorded owner, names without one are dropped from the index so that
+        // they can't hold up pruning
+        let previous_owner = match query_owner_of(&deps.querier, name, &name_c.cw721) {
+            Ok(res) => res.owner,
+            Err(_) => match name_owner_read(deps.storage).may_load(name.as_bytes())? {
+                Some(owner) => owner.to_string(),
+                None => {
+                    top_level_expiration_index(deps.storage).remove(index_key);
+                    response = response.add_attribute("skipped", name);
+                    continue;
+                }
+            },
+        };
+        let (messages, events) = remove_name(&mut deps, &name_c.cw721, name, &previous_owner)?;
+        subdomain_sale(deps.storage).remove(name.as_bytes());
+        pending_dispute(deps.storage).remove(name.as_bytes());
+        response = response
+            .add_submessages(messages)
+            .add_events(events)
+            .add_event(name_pruned_event(name, &previous_owner, &info.sender))
+            .add_attribute("domain", name);
+        pruned += 1;
+    }
+
+    let pool = cleanup_pool_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let bounty = c
+        .cleanup_bounty
+        .checked_mul(Uint128::from(pruned))
+        .map_err(StdError::from)?
+        .min(pool);
+    if !bounty.is_zero() {
+        let balance = pool - bounty;
+        cleanup_pool(deps.storage).save(&balance)?;
+        response = response
+            .add_message(send_tokens(&info.sender, bounty)?)
+            .add_event(cleanup_pool_updated_event(balance));
+    }
+    Ok(response
+        .add_attribute("pruned", pruned.to_string())
+        .add_attribute("bounty", bounty))
 }
 /**
 subdomain rules
-only minted by domain owner
+only minted by domain owner, or by anyone paying the domain owner
+if the domain owner has put its subdomains up for sale
 expiration<= top level domain expiration
 
 when minted only nft owner can set subdomain resolver until expiration
@@ -136,54 +592,102 @@ pub fn execute_set_subdomain(
     new_owner: Addr,
     expiration: u64,
 ) -> Result<Response, ContractError> {
-    let c: Config = config_read(deps.storage).load()?;
+    // subdomains are minted under the domain's tld
+    let c: Config = registration_config(deps.storage, &domain)?;
 
     // Validate top level domain chars
-    validate_name(&domain)?;
-    // Validate subdomain chars
-    validate_subdomain(&subdomain)?;
+    validate_top_level(&domain)?;
+    // Validate new subdomain against the naming rules
+    validate_subdomain(&subdomain, &c.subdomain_rules)?;
     // Formatted subdomain
     let domain_route: String = format!("{}.{}", subdomain, domain);
+    // domains in DNS mode only take DNS compatible subdomains
+    if dns_mode_read(deps.storage)
+        .may_load(domain.as_bytes())?
+        .is_some()
+    {
+        validate_dns_name(&domain_route)?;
+    }
     // Storage key
     let key = domain_route.as_bytes();
     // Check if a domain nft is currently in existence
 
-    // check if doman resolves to a NameRecord throw error otherwise
-    if resolver(deps.storage)
-        .may_load(domain.as_bytes())?
-        .is_none()
-    {
-        return Err(ContractError::InvalidInput {});
-    }
     // load domain Name Record
-    let domain_config: NameRecord = (resolver(deps.storage).may_load(domain.as_bytes())?).unwrap();
+    let domain_config: NameRecord = load_record(deps.storage, &domain)?;
 
     // get the current name owner
-    let owner_response = query_name_owner(&domain, &c.cw721, &deps).unwrap();
+    let owner_response = query_name_owner(&domain, &c.cw721, &deps)?;
 
     if domain_config.is_expired(&env.block) {
         return Err(ContractError::NameOwnershipExpired { name: domain });
     }
+    let mut max_expiration = domain_config.expiration;
+    let mut sale_messages = Vec::new();
+    let mut sale_price = None;
     if owner_response.owner != info.sender {
-        return Err(ContractError::Unauthorized {});
+        // non owners can only buy subdomains the domain owner has for sale
+        let sale = match subdomain_sale_read(deps.storage).may_load(domain.as_bytes())? {
+            Some(sale) => sale,
+            None => return Err(ContractError::SubdomainNotForSale { domain }),
+        };
+        let payment = must_pay(&info, &sale.denom)?;
+        if payment != sale.price {
+            return Err(ContractError::InvalidPayment { amount: payment });
+        }
+        // registry keeps the protocol fee in the sale denom,
+        // the rest goes to the domain owner
+        let fee = sale.price * c.subdomain_fee;
+        let proceeds = sale.price.checked_sub(fee).map_err(StdError::from)?;
+        if !proceeds.is_zero() {
+            sale_messages.push(send_funds(
+                &Addr::unchecked(owner_response.owner),
+                &sale.denom,
+                proceeds,
+            )?);
+        }
+        sale_price = Some((sale.denom.clone(), sale.price));
+        let sale_expiration = env
+            .block
+            .time
+            .seconds()
+            .checked_add(sale.max_duration)
+            .ok_or(ContractError::InvalidInput {})?;
+        if sale_expiration < max_expiration {
+            max_expiration = sale_expiration;
+        }
     }
     //set expiration to domain expiration if subdomain configuration
-    let _expiration = match expiration > domain_config.expiration {
-        true => &domain_config.expiration,
+    let _expiration = match expiration > max_expiration {
+        true => &max_expiration,
         false => &expiration,
     };
 
     let subdomain_status: SubDomainStatus;
     // add subdomain metadata to top level domain but only if hasnt been registerd
-    if resolver(deps.storage).may_load(key).unwrap().is_none() {
+    if resolver(deps.storage).may_load(key)?.is_none() {
         subdomain_status = SubDomainStatus::NewSubdomain;
     } else {
-        match is_expired(&deps, key, &env.block) {
+        match is_expired(&deps, key, &env.block)? {
             true => subdomain_status = SubDomainStatus::ExistingMintExpired,
             false => subdomain_status = SubDomainStatus::ExistingMintActive,
         }
     }
 
+    if subdomain_status == SubDomainStatus::ExistingMintExpired {
+        // fuses burned on an expired subdomain don't carry over to its new owner
+        subdomain_fuses(deps.storage).remove(key);
+    }
+    let event = subdomain_registered_event(
+        &domain,
+        &subdomain,
+        &new_owner,
+        &new_resolver,
+        env.block.time.seconds(),
+        *_expiration,
+        sale_price
+            .as_ref()
+            .map(|(denom, price)| (denom.as_str(), *price)),
+    );
     let messages = match subdomain_status {
         SubDomainStatus::NewSubdomain => register_new_subdomain(
             c.cw721,
@@ -194,7 +698,7 @@ pub fn execute_set_subdomain(
             new_resolver,
             new_owner,
             *_expiration,
-        ),
+        )?,
         SubDomainStatus::ExistingMintExpired => burn_remint_subdomain(
             deps,
             c.cw721,
@@ -204,11 +708,13 @@ pub fn execute_set_subdomain(
             new_resolver,
             new_owner,
             *_expiration,
-        ),
+        )?,
         SubDomainStatus::ExistingMintActive => return Err(ContractError::Unauthorized {}),
     };
     Ok(Response::new()
-        .add_messages(messages.unwrap())
+        .add_messages(messages)
+        .add_messages(sale_messages)
+        .add_event(event)
         .add_attribute("action", "set_subdomain")
         .add_attribute("domain", domain)
         .add_attribute("subdomain", subdomain))
@@ -222,30 +728,17 @@ pub fn execute_extend_subdomain_expiry(
     subdomain: String,
     expiration: u64,
 ) -> Result<Response, ContractError> {
-    validate_name(&domain)?;
+    validate_top_level(&domain)?;
     //
-    validate_subdomain(&subdomain)?;
-    //
-    let c: Config = config_read(deps.storage).load()?;
+    let c: Config = name_config(deps.storage, &domain)?;
     //
     let domain_route: String = format!("{}.{}", subdomain, domain);
 
-    // Check if a domain nft is currently in existence
-
-    // check if doman resolves to a NameRecord throw error otherwise
-
-    if resolver(deps.storage)
-        .may_load(domain_route.as_bytes())?
-        .is_none()
-    {
-        return Err(ContractError::InvalidInput {});
-    }
-    // load domain Name Record
-    let domain_config: NameRecord = (resolver(deps.storage).may_load(domain.as_bytes())?).unwrap();
-    let subdomain_config: NameRecord =
-        (resolver(deps.storage).may_load(domain_route.as_bytes())?).unwrap();
+    // load domain and subdomain Name Records
+    let subdomain_config: NameRecord = load_record(deps.storage, &domain_route)?;
+    let domain_config: NameRecord = load_record(deps.storage, &domain)?;
     // get the current name owner
-    let owner_response = query_name_owner(&domain, &c.cw721, &deps).unwrap();
+    let owner_response = query_name_owner(&domain, &c.cw721, &deps)?;
 
     if domain_config.is_expired(&env.block) {
         return Err(ContractError::NameOwnershipExpired { name: domain });
@@ -253,8 +746,6 @@ pub fn execute_extend_subdomain_expiry(
     if owner_response.owner != info.sender {
         return Err(ContractError::Unauthorized {});
     }
-    //println!("{:?}",subdomain_config.expiration);
-    //println!("{:?}",expiration);
     if expiration <= subdomain_config.expiration {
         return Err(ContractError::InvalidInput {});
     }
@@ -262,10 +753,15 @@ pub fn execute_extend_subdomain_expiry(
         true => domain_config.expiration,
         false => expiration,
     };
+    if _expiration <= subdomain_config.expiration {
+        return Err(ContractError::InvalidInput {});
+    }
+    let event = subdomain_extended_event(&domain, &subdomain, _expiration);
     let messages = update_subdomain_expiry(c.cw721, deps, domain, subdomain, _expiration)?;
 
     Ok(Response::new()
         .add_messages(messages)
+        .add_event(event)
         .add_attribute("action", "extend_subdomain_expiry")
         .add_attribute("domain", domain_route))
 }
@@ -273,17 +769,121 @@ pub fn execute_extend_subdomain_expiry(
 // add reregister function so owners can extend their
 pub fn execute_update_config(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    config_update: Config,
+    mut config_update: Config,
 ) -> Result<Response, ContractError> {
     let c: Config = config_read(deps.storage).load()?;
     if c.admin != info.sender {
         return Err(ContractError::Unauthorized {});
     }
+    if config_update.subdomain_fee > Decimal::one() {
+        return Err(ContractError::InvalidInput {});
+    }
+    if !config_update.name_rules.is_valid() || !config_update.subdomain_rules.is_valid() {
+        return Err(ContractError::InvalidNamingRules {});
+    }
+    // a renewed name must leave the renewal window, or keepers could
+    // renew it again straight away
+    if config_update.renewal_window >= config_update.base_expiration {
+        return Err(ContractError::InvalidInput {});
+    }
+    if config_update.dispute_timelock > MAX_DISPUTE_TIMELOCK {
+        return Err(ContractError::InvalidInput {});
+    }
+    for item in tlds_read(deps.storage).range(None, None, Order::Ascending) {
+        let (_tld, tld_config) = item?;
+        if config_update.renewal_window >= tld_config.base_expiration {
+            return Err(ContractError::InvalidInput {});
+        }
+    }
+
+    // a lower timelock waits out the current one, so that owners keep
+    // the full timelock to appeal disputes scheduled before the change
+    let current_timelock = dispute_timelock(deps.storage, &c, &env.block)?;
+    let mut response = Response::new();
+    if config_update.dispute_timelock < current_timelock {
+        let decrease = match timelock_decrease_read(deps.storage).may_load()? {
+            Some(decrease) if decrease.dispute_timelock == config_update.dispute_timelock => {
+                decrease
+            }
+            _ => TimelockDecrease {
+                dispute_timelock: config_update.dispute_timelock,
+                effective_at: env
+                    .block
+                    .time
+                    .seconds()
+                    .checked_add(current_timelock)
+                    .ok_or(ContractError::InvalidInput {})?,
+            },
+        };
+        timelock_decrease(deps.storage).save(&decrease)?;
+        response = response
+            .add_attribute("dispute_timelock", decrease.dispute_timelock.to_string())
+            .add_attribute("effective_at", decrease.effective_at.to_string());
+        config_update.dispute_timelock = current_timelock;
+    } else if config_update.dispute_timelock > current_timelock {
+        timelock_decrease(deps.storage).remove();
+    }
 
     config(deps.storage).save(&config_update)?;
-    Ok(Response::default())
+    Ok(response
+        .add_event(config_updated_event(&config_update))
+        .add_attribute("action", "update_config"))
+}
+
+// execute_set_tld adds a tld or updates its config, tlds can be disabled
+// but never removed so that their names keep resolving
+pub fn execute_set_tld(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    tld: String,
+    tld_config: TldConfig,
+) -> Result<Response, ContractError> {
+    let c: Config = config_read(deps.storage).load()?;
+    if c.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    // the default tld is configured through UpdateConfig
+    if tld == TLD
+        || tld.is_empty()
+        || tld.len() as u64 > MAX_LABEL_LENGTH
+        || !tld
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    {
+        return Err(ContractError::InvalidTld { tld });
+    }
+    if !tld_config.name_rules.is_valid() || !tld_config.subdomain_rules.is_valid() {
+        return Err(ContractError::InvalidNamingRules {});
+    }
+    if !tld_config.is_valid() || c.renewal_window >= tld_config.base_expiration {
+        return Err(ContractError::InvalidInput {});
+    }
+    match tlds_read(deps.storage).may_load(tld.as_bytes())? {
+        // names already minted would be left on the previous collection
+        Some(prev) if prev.cw721 != tld_config.cw721 => {
+            return Err(ContractError::InvalidInput {});
+        }
+        Some(_) => {}
+        // names like `x.dao` would stop resolving to subdomains of `dao.arch`
+        None => {
+            let label_name = format!("{}.{}", tld, TLD);
+            if resolver_read(deps.storage)
+                .may_load(label_name.as_bytes())?
+                .is_some()
+            {
+                return Err(ContractError::InvalidTld { tld });
+            }
+        }
+    }
+
+    tlds(deps.storage).save(tld.as_bytes(), &tld_config)?;
+    Ok(Response::new()
+        .add_event(tld_updated_event(&tld, &tld_config))
+        .add_attribute("action", "set_tld")
+        .add_attribute("tld", tld))
 }
 pub fn execute_update_resolver(
     info: MessageInfo,
@@ -292,15 +892,13 @@ pub fn execute_update_resolver(
     name: String,
     new_resolver: Addr,
 ) -> Result<Response, ContractError> {
-    let c: Config = config_read(deps.storage).load()?;
+    let c: Config = name_config(deps.storage, &name)?;
+    let curr = load_record(deps.storage, &name)?;
 
     let owner_response = query_name_owner(&name, &c.cw721, &deps)?;
     if owner_response.owner != info.sender {
         return Err(ContractError::Unauthorized {});
     }
-    let subdomain = get_subdomain_prefix(name.clone());
-    let key = name.as_bytes();
-    let curr = (resolver(deps.storage).may_load(key)?).unwrap();
     if curr.is_expired(&env.block) {
         return Err(ContractError::NameOwnershipExpired { name });
     }
@@ -311,36 +909,502 @@ pub fn execute_update_resolver(
         expiration: curr.expiration,
     };
     let mut messages = Vec::new();
-    if let Some(s) = subdomain {
-        //let s = subdomain.unwrap();
-        let resp = update_subdomain_metadata(
+    if let Some((label, parent)) = parent_name(&name) {
+        messages.push(update_subdomain_metadata(
             &deps,
             &c.cw721,
-            &s[1],
-            &s[0],
+            &parent.to_string(),
+            &label.to_string(),
             new_resolver,
             curr.expiration,
-        )?;
-
-        messages.push(resp);
+        )?);
     }
-    resolver(deps.storage).save(key, &record)?;
+    save_record(deps.storage, key, &record)?;
     Ok(Response::new()
         .add_messages(messages)
+        .add_event(resolver_changed_event(&name, &record.resolver))
         .add_attribute("action", "update_resolver")
         .add_attribute("domain", name))
 }
+// execute_sync_ownership points a transferred name at its new owner
+// and clears the records left behind by the previous owner
+pub fn execute_sync_ownership(
+    mut deps: DepsMut,
+    _env: Env,
+    name: String,
+) -> Result<Response, ContractError> {
+    let key = name.as_bytes();
+    let curr = match resolver(deps.storage).may_load(key)? {
+        Some(record) => record,
+        None => return Err(ContractError::NameNotExists { name }),
+    };
+    let c: Config = name_config(deps.storage, &name)?;
+    let owner = Addr::unchecked(query_name_owner(&name, &c.cw721, &deps)?.owner);
+    match name_owner_read(deps.storage).may_load(key)? {
+        Some(prev) if prev == owner => return Err(ContractError::OwnershipInSync { name }),
+        Some(_) => {}
+        // names not yet backfilled have no recorded owner to compare with,
+        // their current owner is recorded without resetting anything
+        None => {
+            save_name_owner(deps.storage, key, &owner)?;
+            return Ok(Response::new()
+                .add_attribute("action", "sync_ownership")
+                .add_attribute("domain", name)
+                .add_attribute("owner", owner));
+        }
+    }
+
+    let (messages, events) = change_owner(&mut deps, &c, &name, &curr, &owner)?;
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_events(events)
+        .add_attribute("action", "sync_ownership")
+        .add_attribute("domain", name)
+        .add_attribute("owner", owner))
+}
+
+// change_owner hands a name over to its new token owner, resolving it to the
+// new owner, clearing the records and settings of the previous one and
+// refunding the previous owner's renewal funds
+fn change_owner(
+    deps: &mut DepsMut,
+    c: &Config,
+    name: &String,
+    curr: &NameRecord,
+    owner: &Addr,
+) -> Result<(Vec<CosmosMsg>, Vec<Event>), ContractError> {
+    let key = name.as_bytes();
+    let previous_owner = escrow_owner(deps, name, &c.cw721)?;
+    let mut messages = Vec::new();
+    let mut metadata: Metadata = query_current_metadata(name, &c.cw721, deps)?;
+    clear_owner_records(&mut metadata);
+    messages.push(send_data_update(name, &c.cw721, metadata)?);
+    if let Some((label, parent)) = parent_name(name) {
+        messages.push(update_subdomain_metadata(
+            deps,
+            &c.cw721,
+            &parent.to_string(),
+            &label.to_string(),
+            owner.clone(),
+            curr.expiration,
+        )?);
+    }
+
+    let record = NameRecord {
+        resolver: owner.clone(),
+        created: curr.created,
+        expiration: curr.expiration,
+    };
+    save_record(deps.storage, key, &record)?;
+    save_name_owner(deps.storage, key, owner)?;
+    subdomain_sale(deps.storage).remove(key);
+    dns_mode(deps.storage).remove(key);
+    dns_records(deps.storage).remove(key);
+    let mut events = vec![
+        owner_changed_event(name, owner),
+        resolver_changed_event(name, owner),
+    ];
+    if let Some((refund, event)) = refund_renewal_funds(deps, name, previous_owner.as_str())? {
+        messages.push(refund);
+        events.push(event);
+    }
+    Ok((messages, events))
+}
+
+// escrow_owner returns the owner a name's renewal funds belong to, its recorded
+// owner until a transfer is synced, or its token owner if none is recorded yet
+fn escrow_owner(deps: &DepsMut, name: &str, cw721: &Addr) -> Result<Addr, ContractError> {
+    match name_owner_read(deps.storage).may_load(name.as_bytes())? {
+        Some(owner) => Ok(owner),
+        None => Ok(Addr::unchecked(query_name_owner(name, cw721, deps)?.owner)),
+    }
+}
+// execute_reconcile rewrites the cw721 metadata of names whose token
+// has drifted from the registry's records
+pub fn execute_reconcile(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    names: Vec<String>,
+) -> Result<Response, ContractError> {
+    let c: Config = config_read(deps.storage).load()?;
+    if info.sender != c.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if names.is_empty() {
+        return Err(ContractError::InvalidInput {});
+    }
+    let mut messages = Vec::new();
+    let mut response = Response::new().add_attribute("action", "reconcile");
+    for name in names {
+        let record = match resolver_read(deps.storage).may_load(name.as_bytes())? {
+            Some(record) => record,
+            None => return Err(ContractError::NameNotExists { name }),
+        };
+        let cw721 = name_config(deps.storage, &name)?.cw721;
+        messages.push(reconcile_metadata(&deps, &cw721, &name, &record)?);
+        response = response
+            .add_event(metadata_updated_event(&name))
+            .add_attribute("domain", name);
+    }
+    Ok(response.add_messages(messages))
+}
+// execute_set_reserved_names reserves names for the admin to register,
+// reserving a registered name only applies once it can be registered again
+pub fn execute_set_reserved_names(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    names: Vec<String>,
+    reserved: bool,
+) -> Result<Response, ContractError> {
+    let c: Config = config_read(deps.storage).load()?;
+    if info.sender != c.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    validate_batch(&names, validate_top_level)?;
+    let mut response = Response::new()
+        .add_attribute("action", "set_reserved_names")
+        .add_attribute("reserved", reserved.to_string());
+    for name in names {
+        if reserved {
+            reserved_names(deps.storage).save(name.as_bytes(), &true)?;
+        } else {
+            reserved_names(deps.storage).remove(name.as_bytes());
+        }
+        response = response
+            .add_event(name_reserved_event(&name, reserved))
+            .add_attribute("domain", name);
+    }
+    Ok(response)
+}
+
+// execute_backfill_indexes indexes a page of names saved before the expiration,
+// subdomain and owner indexes existed, and records their current token owners
+// for SyncOwnership. Pages continue after the `last_indexed` attribute
+pub fn execute_backfill_indexes(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let c: Config = config_read(deps.storage).load()?;
+    if info.sender != c.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|name| [name.as_bytes(), &[0]].concat());
+    let records: StdResult<Vec<Record<NameRecord>>> = resolver_read(deps.storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .collect();
+
+    let mut indexed = 0;
+    let mut last_indexed = None;
+    for (key, record) in records? {
+        save_record(deps.storage, &key, &record)?;
+        let name = String::from_utf8(key.clone()).map_err(StdError::from)?;
+        match name_owner_read(deps.storage).may_load(&key)? {
+            // owners recorded since are left for SyncOwnership
+            Some(owner) => save_name_owner(deps.storage, &key, &owner)?,
+            // the current owner is recorded without resetting anything,
+            // a resolver pointing elsewhere may well be the owner's choice
+            None => {
+                let name_c = name_config(deps.storage, &name)?;
+                if let Ok(res) = query_owner_of(&deps.querier, &name, &name_c.cw721) {
+                    save_name_owner(deps.storage, &key, &Addr::unchecked(res.owner))?;
+                }
+            }
+        }
+        indexed += 1;
+        last_indexed = Some(name);
+    }
+    let mut response = Response::new()
+        .add_attribute("action", "backfill_indexes")
+        .add_attribute("indexed", indexed.to_string());
+    if let Some(name) = last_indexed {
+        response = response.add_attribute("last_indexed", name);
+    }
+    Ok(response)
+}
+// execute_revoke_name removes a disputed name and its subdomains
+pub fn execute_revoke_name(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    schedule_dispute(deps, env, info, name, DisputeAction::Revoke { reason })
+}
+
+// execute_reassign_name hands a disputed name to a new owner, keeping its expiration
+pub fn execute_reassign_name(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    new_owner: Addr,
+) -> Result<Response, ContractError> {
+    schedule_dispute(deps, env, info, name, DisputeAction::Reassign { new_owner })
+}
+
+// disputes apply straight away without a timelock, otherwise they wait
+// to be executed once the timelock has passed
+fn schedule_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    action: DisputeAction,
+) -> Result<Response, ContractError> {
+    let c: Config = config_read(deps.storage).load()?;
+    if info.sender != c.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let key = name.as_bytes();
+    if resolver_read(deps.storage).may_load(key)?.is_none() {
+        return Err(ContractError::NameNotExists { name });
+    }
+    if pending_dispute_read(deps.storage).may_load(key)?.is_some() {
+        return Err(ContractError::DisputePending { name });
+    }
+    let timelock = dispute_timelock(deps.storage, &c, &env.block)?;
+    if timelock == 0 {
+        let c = name_config(deps.storage, &name)?;
+        return apply_dispute(deps, &c, &info.sender, name, action);
+    }
+    let executable_at = env
+        .block
+        .time
+        .seconds()
+        .checked_add(timelock)
+        .ok_or(ContractError::InvalidInput {})?;
+    pending_dispute(deps.storage).save(
+        key,
+        &PendingDispute {
+            action: action.clone(),
+            executable_at,
+        },
+    )?;
+    Ok(dispute_attributes(Response::new(), &action)
+        .add_event(dispute_scheduled_event(
+            &name,
+            action.as_str(),
+            executable_at,
+        ))
+        .add_attribute("domain", name)
+        .add_attribute("admin", info.sender)
+        .add_attribute("status", "scheduled")
+        .add_attribute("executable_at", executable_at.to_string()))
+}
+
+pub fn execute_apply_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    let c: Config = config_read(deps.storage).load()?;
+    if info.sender != c.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let key = name.as_bytes();
+    let dispute = match pending_dispute_read(deps.storage).may_load(key)? {
+        Some(dispute) => dispute,
+        None => return Err(ContractError::NoPendingDispute { name }),
+    };
+    if env.block.time.seconds() < dispute.executable_at {
+        return Err(ContractError::DisputeTimelocked {
+            name,
+            executable_at: dispute.executable_at,
+        });
+    }
+    pending_dispute(deps.storage).remove(key);
+    let c = name_config(deps.storage, &name)?;
+    apply_dispute(deps, &c, &info.sender, name, dispute.action)
+}
+
+// pending disputes can only be cancelled by the admin, the timelock is the
+// owner's window to appeal, not to veto
+pub fn execute_cancel_dispute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    let key = name.as_bytes();
+    let dispute = match pending_dispute_read(deps.storage).may_load(key)? {
+        Some(dispute) => dispute,
+        None => return Err(ContractError::NoPendingDispute { name }),
+    };
+    let c: Config = config_read(deps.storage).load()?;
+    if info.sender != c.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    pending_dispute(deps.storage).remove(key);
+    Ok(dispute_attributes(Response::new(), &dispute.action)
+        .add_event(dispute_cancelled_event(&name, dispute.action.as_str()))
+        .add_attribute("domain", name)
+        .add_attribute("admin", info.sender)
+        .add_attribute("status", "cancelled"))
+}
+
+fn dispute_attributes(response: Response, action: &DisputeAction) -> Response {
+    match action {
+        DisputeAction::Revoke { reason } => response
+            .add_attribute("action", "revoke_name")
+            .add_attribute("reason", reason),
+        DisputeAction::Reassign { new_owner } => response
+            .add_attribute("action", "reassign_name")
+            .add_attribute("new_owner", new_owner),
+    }
+}
+
+// remove_name burns a name and its subdomains and removes their records,
+// refunding the name's unspent renewal funds to its previous owner
+fn remove_name(
+    deps: &mut DepsMut,
+    cw721: &Addr,
+    name: &String,
+    previous_owner: &str,
+) -> Result<(Vec<SubMsg>, Vec<Event>), ContractError> {
+    let key = name.as_bytes();
+    let mut messages = Vec::new();
+    let mut events = Vec::new();
+    for (label, _record) in subdomain_records(deps.storage, name)? {
+        let subdomain = format!("{}.{}", label, name);
+        messages.push(burn_submsg(&subdomain, cw721)?);
+        remove_record(deps.storage, subdomain.as_bytes())?;
+        subdomain_fuses(deps.storage).remove(subdomain.as_bytes());
+        events.push(subdomain_removed_event(name, &label));
+    }
+    if let Some((label, parent)) = parent_name(name) {
+        messages.push(SubMsg::new(remove_subdomain_metadata(
+            deps,
+            cw721,
+            parent.to_string(),
+            label.to_string(),
+        )?));
+    }
+    if let Some((refund, event)) = refund_renewal_funds(deps, name, previous_owner)? {
+        messages.push(SubMsg::new(refund));
+        events.push(event);
+    }
+    messages.push(burn_submsg(name, cw721)?);
+    remove_record(deps.storage, key)?;
+    subdomain_fuses(deps.storage).remove(key);
+    Ok((messages, events))
+}
+
+// refund_renewal_funds sends a name's unspent renewal funds back
+// to the owner who deposited them and empties its escrow
+fn refund_renewal_funds(
+    deps: &mut DepsMut,
+    name: &String,
+    previous_owner: &str,
+) -> Result<Option<(CosmosMsg, Event)>, ContractError> {
+    let key = name.as_bytes();
+    let balance = match renewal_funds_read(deps.storage).may_load(key)? {
+        Some(balance) => balance,
+        None => return Ok(None),
+    };
+    let refund = send_tokens(&Addr::unchecked(previous_owner), balance)?;
+    save_renewal_funds(deps.storage, key, Uint128::zero())?;
+    Ok(Some((
+        refund,
+        renewal_funds_updated_event(name, Uint128::zero()),
+    )))
+}
+
+fn apply_dispute(
+    mut deps: DepsMut,
+    c: &Config,
+    admin: &Addr,
+    name: String,
+    action: DisputeAction,
+) -> Result<Response, ContractError> {
+    let key = name.as_bytes();
+    let curr = match resolver_read(deps.storage).may_load(key)? {
+        Some(record) => record,
+        None => return Err(ContractError::NameNotExists { name }),
+    };
+    let previous_owner = query_name_owner(&name, &c.cw721, &deps)?.owner;
+    let mut messages = Vec::new();
+    let mut events = Vec::new();
+    match &action {
+        DisputeAction::Revoke { reason } => {
+            let (removed_messages, removed_events) =
+                remove_name(&mut deps, &c.cw721, &name, &previous_owner)?;
+            messages.extend(removed_messages);
+            events.extend(removed_events);
+            events.push(name_revoked_event(&name, reason, &previous_owner));
+        }
+        DisputeAction::Reassign { new_owner } => {
+            messages.push(SubMsg::new(burn_handler(&name, &c.cw721)?));
+            messages.push(SubMsg::new(mint_handler(
+                &name,
+                new_owner,
+                &c.cw721,
+                curr.created,
+                curr.expiration,
+            )?));
+            let (owner_messages, owner_events) =
+                change_owner(&mut deps, c, &name, &curr, new_owner)?;
+            messages.extend(owner_messages.into_iter().map(SubMsg::new));
+            events.extend(owner_events);
+        }
+    }
+    subdomain_sale(deps.storage).remove(key);
+    dns_records(deps.storage).remove(key);
+    Ok(dispute_attributes(Response::new(), &action)
+        .add_submessages(messages)
+        .add_events(events)
+        .add_attribute("domain", name)
+        .add_attribute("admin", admin)
+        .add_attribute("status", "executed")
+        .add_attribute("previous_owner", previous_owner))
+}
+// execute_withdraw_fees sends protocol fees to the wallet,
+// escrowed renewal funds and the cleanup pool stay in the registry
 pub fn execute_withdraw_fees(
     info: MessageInfo,
     deps: DepsMut,
+    env: Env,
     amount: Uint128,
+    denom: Option<String>,
 ) -> Result<Response, ContractError> {
     let c: Config = config_read(deps.storage).load()?;
     if c.admin != info.sender {
         return Err(ContractError::Unauthorized {});
     }
-    let resp = send_tokens(&c.wallet, amount)?;
-    Ok(Response::new().add_message(resp))
+    let denom = denom.unwrap_or_else(|| String::from(DENOM));
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &denom)?
+        .amount;
+    // escrow and the cleanup pool are only ever held in DENOM
+    let available = match denom == DENOM {
+        true => {
+            let escrow = renewal_funds_total_read(deps.storage)
+                .may_load()?
+                .unwrap_or_default();
+            let pool = cleanup_pool_read(deps.storage)
+                .may_load()?
+                .unwrap_or_default();
+            balance.saturating_sub(escrow).saturating_sub(pool)
+        }
+        false => balance,
+    };
+    if amount > available {
+        return Err(ContractError::InsufficientFees { available });
+    }
+    let resp = send_funds(&c.wallet, &denom, amount)?;
+    Ok(Response::new()
+        .add_message(resp)
+        .add_event(withdraw_event(&c.wallet, amount, &denom))
+        .add_attribute("action", "withdraw"))
 }
 
 pub fn execute_user_metadata_update(
@@ -349,17 +1413,19 @@ pub fn execute_user_metadata_update(
     name: String,
     update: MetaDataUpdateMsg,
 ) -> Result<Response, ContractError> {
-    let c: Config = config_read(deps.storage).load()?;
+    let c: Config = name_config(deps.storage, &name)?;
     let cw721 = c.cw721;
-    let owner_response = query_name_owner(&name, &cw721, &deps).unwrap();
+    load_record(deps.storage, &name)?;
+    let owner_response = query_name_owner(&name, &cw721, &deps)?;
 
     if owner_response.owner != info.sender {
         return Err(ContractError::Unauthorized {});
     }
-    let current_metadata: Metadata = query_current_metadata(&name, &cw721, &deps).unwrap();
+    let current_metadata: Metadata = query_current_metadata(&name, &cw721, &deps)?;
     let new_metadata = Metadata {
         description: update.clone().description,
-        name: Some(name.clone()),
+        // the unicode name without its tld, as minted
+        name: Some(to_unicode(&get_name_body(name.clone()))),
         image: update.clone().image,
         created: current_metadata.created,
         expiry: current_metadata.expiry,
@@ -371,6 +1437,7 @@ pub fn execute_user_metadata_update(
     let resp = send_data_update(&name, &cw721, new_metadata);
     Ok(Response::new()
         .add_messages(resp)
+        .add_event(metadata_updated_event(&name))
         .add_attribute("action", "metadata_update")
         .add_attribute("domain", name))
 }
@@ -381,22 +1448,31 @@ pub fn execute_remove_subdomain(
     domain: String,
     subdomain: String,
 ) -> Result<Response, ContractError> {
-    let c: Config = config_read(deps.storage).load()?;
+    let c: Config = name_config(deps.storage, &domain)?;
     let domain_route = format!("{}.{}", subdomain, domain);
     let key = domain_route.as_bytes();
     let mut messages = Vec::new();
 
-    let owner_response = query_name_owner(&domain, &c.cw721, &deps).unwrap();
-    resolver(deps.storage).remove(key);
+    load_record(deps.storage, &domain_route)?;
+    let owner_response = query_name_owner(&domain, &c.cw721, &deps)?;
+    let fuses = active_fuses(deps.storage, &domain_route, &env.block)?;
+    if fuses & (CANNOT_REMOVE | PARENT_CANNOT_CONTROL) != 0 {
+        return Err(ContractError::FuseBurned {
+            name: domain_route,
+            fuses,
+        });
+    }
     if owner_response.owner != info.sender {
         return Err(ContractError::Unauthorized {});
     }
-    let subdomain_owner = query_name_owner(&domain_route, &c.cw721, &deps).unwrap();
+    let subdomain_owner = query_name_owner(&domain_route, &c.cw721, &deps)?;
     // if owner of the minted subdomain is not owner of the top level domain
     // and subdomain is not expired
-    if !is_expired(&deps, key, &env.block) && subdomain_owner.owner != info.sender {
+    if !is_expired(&deps, key, &env.block)? && subdomain_owner.owner != info.sender {
         return Err(ContractError::NameTaken { name: domain_route });
     }
+    remove_record(deps.storage, key)?;
+    subdomain_fuses(deps.storage).remove(key);
     messages.push(remove_subdomain_metadata(
         &deps,
         &c.cw721,
@@ -407,7 +1483,180 @@ pub fn execute_remove_subdomain(
 
     Ok(Response::new()
         .add_messages(messages)
+        .add_event(subdomain_removed_event(&domain, &subdomain))
         .add_attribute("action", "remove_subdomain")
         .add_attribute("domain", domain)
         .add_attribute("subdomain", subdomain))
 }
+
+pub fn execute_set_subdomain_sale(
+    info: MessageInfo,
+    deps: DepsMut,
+    env: Env,
+    domain: String,
+    sale: Option<SubdomainSale>,
+) -> Result<Response, ContractError> {
+    validate_top_level(&domain)?;
+    let c: Config = name_config(deps.storage, &domain)?;
+    let key = domain.as_bytes();
+    let domain_config = match resolver(deps.storage).may_load(key)? {
+        Some(record) => record,
+        None => return Err(ContractError::NameNotExists { name: domain }),
+    };
+    if domain_config.is_expired(&env.block) {
+        return Err(ContractError::NameOwnershipExpired { name: domain });
+    }
+    let owner_response = query_name_owner(&domain, &c.cw721, &deps)?;
+    if owner_response.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    match &sale {
+        Some(sale) => {
+            if sale.price.is_zero() || sale.denom.is_empty() || sale.max_duration == 0 {
+                return Err(ContractError::InvalidInput {});
+            }
+            // subdomains can't outlive the longest domain registration
+            if sale.max_duration > interval_expiration(&c, c.name_rules.max_intervals, 0)? {
+                return Err(ContractError::InvalidInput {});
+            }
+            subdomain_sale(deps.storage).save(key, sale)?;
+        }
+        None => subdomain_sale(deps.storage).remove(key),
+    }
+    Ok(Response::new()
+        .add_event(subdomain_sale_updated_event(&domain, sale.as_ref()))
+        .add_attribute("action", "set_subdomain_sale")
+        .add_attribute("domain", domain))
+}
+
+pub fn execute_burn_subdomain_fuses(
+    info: MessageInfo,
+    deps: DepsMut,
+    env: Env,
+    domain: String,
+    subdomain: String,
+    fuses: u32,
+) -> Result<Response, ContractError> {
+    validate_top_level(&domain)?;
+    if fuses == 0 || fuses & !ALL_FUSES != 0 {
+        return Err(ContractError::InvalidInput {});
+    }
+    let c: Config = name_config(deps.storage, &domain)?;
+    let domain_route: String = format!("{}.{}", subdomain, domain);
+    let key = domain_route.as_bytes();
+
+    let domain_config = match resolver(deps.storage).may_load(domain.as_bytes())? {
+        Some(record) => record,
+        None => return Err(ContractError::NameNotExists { name: domain }),
+    };
+    if domain_config.is_expired(&env.block) {
+        return Err(ContractError::NameOwnershipExpired { name: domain });
+    }
+    // fuses can only be burned on active subdomains
+    if is_expired(&deps, key, &env.block)? {
+        return Err(ContractError::NameOwnershipExpired { name: domain_route });
+    }
+    let owner_response = query_name_owner(&domain, &c.cw721, &deps)?;
+    if owner_response.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let burned = active_fuses(deps.storage, &domain_route, &env.block)?;
+    if burned & PARENT_CANNOT_CONTROL != 0 {
+        return Err(ContractError::FuseBurned {
+            name: domain_route,
+            fuses: burned,
+        });
+    }
+    let burned = burned | fuses;
+    subdomain_fuses(deps.storage).save(key, &burned)?;
+
+    Ok(Response::new()
+        .add_event(subdomain_fuses_burned_event(&domain_route, burned))
+        .add_attribute("action", "burn_subdomain_fuses")
+        .add_attribute("domain", domain_route)
+        .add_attribute("fuses", burned.to_string()))
+}
+
+// execute_set_dns_mode opts a domain in to DNS compatible labels,
+// the domain and all of its subdomains must already be DNS compatible
+pub fn execute_set_dns_mode(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    validate_top_level(&name)?;
+    let c: Config = name_config(deps.storage, &name)?;
+    let key = name.as_bytes();
+    let curr = match resolver_read(deps.storage).may_load(key)? {
+        Some(record) => record,
+        None => return Err(ContractError::NameNotExists { name }),
+    };
+    if curr.is_expired(&env.block) {
+        return Err(ContractError::NameOwnershipExpired { name });
+    }
+    let owner_response = query_name_owner(&name, &c.cw721, &deps)?;
+    if owner_response.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if enabled {
+        validate_dns_name(&name)?;
+        for (label, _record) in subdomain_records(deps.storage, &name)? {
+            validate_dns_name(&format!("{}.{}", label, name))?;
+        }
+        dns_mode(deps.storage).save(key, &true)?;
+    } else {
+        dns_mode(deps.storage).remove(key);
+    }
+    Ok(Response::new()
+        .add_event(dns_mode_updated_event(&name, enabled))
+        .add_attribute("action", "set_dns_mode")
+        .add_attribute("domain", name)
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+// execute_set_dns_records replaces the records of a name,
+// an empty record set removes them
+pub fn execute_set_dns_records(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    records: Vec<DnsRecord>,
+) -> Result<Response, ContractError> {
+    let c: Config = name_config(deps.storage, &name)?;
+    let key = name.as_bytes();
+    let curr = match resolver_read(deps.storage).may_load(key)? {
+        Some(record) => record,
+        None => return Err(ContractError::NameNotExists { name }),
+    };
+    if curr.is_expired(&env.block) {
+        return Err(ContractError::NameOwnershipExpired { name });
+    }
+    let owner_response = query_name_owner(&name, &c.cw721, &deps)?;
+    if owner_response.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let domain = match parent_name(&name) {
+        Some((_label, parent)) => parent.to_string(),
+        None => name.clone(),
+    };
+    if dns_mode_read(deps.storage)
+        .may_load(domain.as_bytes())?
+        .is_none()
+    {
+        return Err(ContractError::DnsModeDisabled { name: domain });
+    }
+    let records = normalize_records(records)?;
+    if records.is_empty() {
+        dns_records(deps.storage).remove(key);
+    } else {
+        dns_records(deps.storage).save(key, &records)?;
+    }
+    Ok(Response::new()
+        .add_event(dns_records_updated_event(&name, &records))
+        .add_attribute("action", "set_dns_records")
+        .add_attribute("domain", name)
+        .add_attribute("records", records.len().to_string()))
+}
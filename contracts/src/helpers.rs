@@ -0,0 +1,169 @@
+// Typed access to a deployed registry for other contracts, e.g.
+// `ArchIdRegistry(registry_addr).resolve(&deps.querier, "simpletest.arch")`
+// Names are normalized by the registry, so queries and execute messages alike
+// accept `.arch` names without their tld, the same as the registry's own messages.
+use cosmwasm_std::{
+    to_json_binary, Addr, Coin, CosmosMsg, QuerierWrapper, QueryRequest, StdResult, WasmMsg,
+    WasmQuery,
+};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::msg::{
+    AvailabilityResponse, DnsZoneResponse, ExecuteMsg, NameInfoResponse, NamesByOwnerResponse,
+    QueryMsg, RecordExpirationResponse, ResolveAddressResponse, ResolveRecordResponse,
+    TldsResponse,
+};
+use crate::state::Config;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ArchIdRegistry(pub Addr);
+
+impl ArchIdRegistry {
+    pub fn addr(&self) -> Addr {
+        self.0.clone()
+    }
+
+    pub fn call(&self, msg: ExecuteMsg, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+            funds,
+        }
+        .into())
+    }
+
+    // names without a tld are registered as `{name}.arch`,
+    // funds must cover the registration
+    pub fn register_msg(&self, name: &str, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
+        self.call(
+            ExecuteMsg::Register {
+                name: name.to_string(),
+            },
+            funds,
+        )
+    }
+
+    pub fn renew_msg(&self, name: &str, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
+        self.call(
+            ExecuteMsg::RenewRegistration {
+                name: name.to_string(),
+            },
+            funds,
+        )
+    }
+
+    pub fn query<T: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper,
+        msg: &QueryMsg,
+    ) -> StdResult<T> {
+        let req = QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(msg)?,
+        });
+        querier.query(&req)
+    }
+
+    // address is None once the name has expired
+    pub fn resolve(
+        &self,
+        querier: &QuerierWrapper,
+        name: &str,
+    ) -> StdResult<ResolveRecordResponse> {
+        self.query(
+            querier,
+            &QueryMsg::ResolveRecord {
+                name: name.to_string(),
+            },
+        )
+    }
+
+    // names resolving to address
+    pub fn reverse(
+        &self,
+        querier: &QuerierWrapper,
+        address: &Addr,
+    ) -> StdResult<ResolveAddressResponse> {
+        self.query(
+            querier,
+            &QueryMsg::ResolveAddress {
+                address: address.clone(),
+            },
+        )
+    }
+
+    pub fn expiration(
+        &self,
+        querier: &QuerierWrapper,
+        name: &str,
+    ) -> StdResult<RecordExpirationResponse> {
+        self.query(
+            querier,
+            &QueryMsg::RecordExpiration {
+                name: name.to_string(),
+            },
+        )
+    }
+
+    pub fn name_info(&self, querier: &QuerierWrapper, name: &str) -> StdResult<NameInfoResponse> {
+        self.query(
+            querier,
+            &QueryMsg::NameInfo {
+                name: name.to_string(),
+            },
+        )
+    }
+
+    pub fn config(&self, querier: &QuerierWrapper) -> StdResult<Config> {
+        self.query(querier, &QueryMsg::Config {})
+    }
+
+    // records served for a name, empty unless its domain is in DNS mode
+    pub fn dns_zone(&self, querier: &QuerierWrapper, name: &str) -> StdResult<DnsZoneResponse> {
+        self.query(
+            querier,
+            &QueryMsg::DnsZone {
+                name: name.to_string(),
+            },
+        )
+    }
+
+    // whether a name can be registered, and why not
+    pub fn availability(
+        &self,
+        querier: &QuerierWrapper,
+        name: &str,
+    ) -> StdResult<AvailabilityResponse> {
+        self.query(
+            querier,
+            &QueryMsg::Availability {
+                name: name.to_string(),
+            },
+        )
+    }
+
+    // names owned by an address, as last synced with the cw721 contract
+    pub fn names_by_owner(
+        &self,
+        querier: &QuerierWrapper,
+        owner: &Addr,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<NamesByOwnerResponse> {
+        self.query(
+            querier,
+            &QueryMsg::NamesByOwner {
+                owner: owner.clone(),
+                start_after,
+                limit,
+                include_expired: None,
+            },
+        )
+    }
+
+    // `.arch` followed by the tlds added by the admin
+    pub fn tlds(&self, querier: &QuerierWrapper) -> StdResult<TldsResponse> {
+        self.query(querier, &QueryMsg::Tlds {})
+    }
+}
@@ -0,0 +1,90 @@
+// Internationalized names. Labels are normalized with UTS-46 and stored,
+// minted and resolved under their ASCII (punycode `xn--`) form, the Unicode
+// form is only used for display.
+use idna::Config as IdnaConfig;
+use unicode_security::confusable_detection::skeleton;
+use unicode_security::{RestrictionLevel, RestrictionLevelDetection};
+
+use crate::error::ContractError;
+
+pub const ACE_PREFIX: &str = "xn--";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdnName {
+    pub ascii: String,
+    pub unicode: String,
+}
+
+// non-transitional UTS-46 processing with the STD3 (LDH) rules,
+// so e.g. `ß` is kept rather than mapped to `ss`
+fn idna_config() -> IdnaConfig {
+    IdnaConfig::default()
+        .use_std3_ascii_rules(true)
+        .transitional_processing(false)
+        .check_hyphens(true)
+        .verify_dns_length(true)
+}
+
+/// normalize_label returns the ASCII and Unicode forms of a single label.
+/// Plain ASCII labels are returned as they are and left to the existing
+/// character rules, Unicode and `xn--` labels must normalize cleanly and
+/// pass the script checks
+pub fn normalize_label(label: &str) -> Result<IdnName, ContractError> {
+    if label.is_ascii() && !label.starts_with(ACE_PREFIX) {
+        return Ok(IdnName {
+            ascii: label.to_string(),
+            unicode: label.to_string(),
+        });
+    }
+    let invalid = || ContractError::InvalidUnicodeName {
+        name: label.to_string(),
+    };
+    let ascii = idna_config().to_ascii(label).map_err(|_| invalid())?;
+    // full stop variants such as U+3002 are mapped to `.` and would
+    // silently turn one label into two
+    if ascii.is_empty() || ascii.contains('.') {
+        return Err(invalid());
+    }
+    // punycode input must already be in its canonical form, otherwise
+    // several token ids could resolve to the same name
+    if label.is_ascii() && ascii != label {
+        return Err(invalid());
+    }
+    let (unicode, res) = idna_config().to_unicode(&ascii);
+    res.map_err(|_| invalid())?;
+    check_script(&unicode)?;
+    Ok(IdnName { ascii, unicode })
+}
+
+/// to_unicode returns the display form of a stored name
+pub fn to_unicode(name: &str) -> String {
+    name.split('.')
+        .map(|label| match label.starts_with(ACE_PREFIX) {
+            true => idna_config().to_unicode(label).0,
+            false => label.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+// labels may combine scripts only the way a single writing system does
+// (e.g. Han with Hiragana and Katakana), and may not imitate an ASCII name
+fn check_script(label: &str) -> Result<(), ContractError> {
+    match label.detect_restriction_level() {
+        RestrictionLevel::ASCIIOnly
+        | RestrictionLevel::SingleScript
+        | RestrictionLevel::HighlyRestrictive => {}
+        _ => {
+            return Err(ContractError::MixedScriptName {
+                label: label.to_string(),
+            })
+        }
+    }
+    // e.g. cyrillic `аррӏе` has the skeleton `apple`
+    if !label.is_ascii() && skeleton(label).all(|c| c.is_ascii()) {
+        return Err(ContractError::ConfusableName {
+            label: label.to_string(),
+        });
+    }
+    Ok(())
+}
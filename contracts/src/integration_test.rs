@@ -1,20 +1,48 @@
 #![cfg(test)]
 use cosmwasm_std::{
-    to_binary, Addr, Coin, Empty, QueryRequest, StdError, Timestamp, Uint128, WasmQuery,
+    to_binary, Addr, Coin, Decimal, Event, QueryRequest, StdError, Timestamp, Uint128, WasmQuery,
 };
-use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_multi_test::{App, AppResponse, Executor};
+use proptest::prelude::*;
 use serde::{de::DeserializeOwned, Serialize};
+use std::convert::TryFrom;
 
 use archid_token::{
-    Extension, InstantiateMsg as Cw721InstantiateMsg, Metadata, QueryMsg as Cw721QueryMsg,
+    Account, ExecuteMsg as Cw721ExecuteMsg, Extension, InstantiateMsg as Cw721InstantiateMsg,
+    Metadata, QueryMsg as Cw721QueryMsg, Subdomain, UpdateMetadataMsg,
 };
 use cw721_updatable::{NftInfoResponse, NumTokensResponse, OwnerOfResponse};
 
+use crate::dns::MAX_DNS_RECORDS;
+use crate::error::ContractError;
+use crate::events::{
+    CleanupPoolUpdatedEvent, ConfigUpdatedEvent, DisputeCancelledEvent, DisputeScheduledEvent,
+    DnsModeUpdatedEvent, DnsRecordsUpdatedEvent, MetadataUpdatedEvent, NamePrunedEvent,
+    NameRevokedEvent, OwnerChangedEvent, RegisterEvent, RegistryEvent, RenewEvent,
+    RenewalFundsUpdatedEvent, ResolverChangedEvent, SubdomainExtendedEvent,
+    SubdomainFusesBurnedEvent, SubdomainRegisteredEvent, SubdomainRemovedEvent,
+    SubdomainSaleUpdatedEvent, TldUpdatedEvent, WithdrawEvent, RENEW_EVENT,
+};
+use crate::helpers::ArchIdRegistry;
 use crate::msg::{
-    ExecuteMsg, InstantiateMsg, QueryMsg, RecordExpirationResponse, ResolveAddressResponse,
-    ResolveRecordResponse,
+    Availability, AvailabilityResponse, BatchAvailabilityResponse, CleanupPoolResponse,
+    ConsistencyIssue, DnsZoneResponse, ExecuteMsg, ExpiringBetweenResponse, InstantiateMsg,
+    MetaDataUpdateMsg, NameInfoResponse, NameMismatch, NameStatus, NameSummary,
+    NamesByOwnerResponse, PendingDisputeResponse, QueryMsg, RecordExpirationResponse,
+    RenewalFundsResponse, ResolveAddressResponse, ResolveRecordResponse, SearchPrefixResponse,
+    SearchScope, SubdomainFusesResponse, SubdomainSaleResponse, SubdomainsResponse, TldsResponse,
+    VerifyConsistencyResponse,
+};
+use crate::parser::{NameParser, MAX_DEPTH};
+use crate::read_utils::{
+    get_name_body, invalid_char, validate_name, validate_subdomain, validate_top_level,
 };
-use crate::state::Config;
+use crate::state::{
+    Config, DisputeAction, DnsRecord, DnsRecordData, NamingRules, PendingDispute, SubDomainStatus,
+    SubdomainSale, TldConfig, CANNOT_REMOVE, MAX_DISPUTE_TIMELOCK, MAX_LABEL_LENGTH,
+    PARENT_CANNOT_CONTROL,
+};
+use crate::testing::{contract_archid, contract_cw721, RegistrySuite};
 use crate::write_utils::DENOM;
 
 fn mock_app() -> App {
@@ -29,22 +57,6 @@ fn increment_block_time(router: &mut App, new_time: u64, height_incr: u64) {
     curr.time = Timestamp::from_seconds(new_time);
     router.set_block(curr);
 }
-pub fn contract_cw721() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(
-        archid_token::entry::execute,
-        archid_token::entry::instantiate,
-        archid_token::entry::query,
-    );
-    Box::new(contract)
-}
-pub fn contract_archid() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(
-        crate::contract::execute,
-        crate::contract::instantiate,
-        crate::contract::query,
-    );
-    Box::new(contract)
-}
 fn create_name_service(
     router: &mut App,
     owner: Addr,
@@ -144,6 +156,15 @@ fn basic_domain_test() {
         cw721: nft.clone(),
         base_cost: Uint128::from(5000u64),
         base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
     };
     let update_msg = ExecuteMsg::UpdateConfig {
         config: update_config,
@@ -378,6 +399,15 @@ fn test_expired_domains() {
         cw721: nft.clone(),
         base_cost: Uint128::from(5000u64),
         base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
     };
     let update_msg = ExecuteMsg::UpdateConfig {
         config: update_config,
@@ -512,6 +542,15 @@ fn test_subdomain_rules() {
         cw721: nft.clone(),
         base_cost: Uint128::from(5000u64),
         base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
     };
     let update_msg = ExecuteMsg::UpdateConfig {
         config: update_config,
@@ -625,6 +664,15 @@ fn test_remint_subdomain() {
         cw721: nft.clone(),
         base_cost: Uint128::from(5000u64),
         base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
     };
     let update_msg = ExecuteMsg::UpdateConfig {
         config: update_config,
@@ -785,6 +833,15 @@ fn test_renewing_domains() {
         cw721: nft.clone(),
         base_cost: base_cost.clone(),
         base_expiration,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
     };
     let update_msg = ExecuteMsg::UpdateConfig {
         config: update_config,
@@ -903,3 +960,5013 @@ fn test_renewing_domains() {
     // Name resolution must not have been overwritten from original value
     assert_eq!(name_resolution.address.unwrap(), name_resolver);
 }
+
+#[test]
+fn test_subdomain_sales() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    let buyer = Addr::unchecked("buyer");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    mint_native(
+        &mut app,
+        buyer.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration: 86400,
+        subdomain_fee: Decimal::percent(10),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config,
+    };
+    let result = app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+    assert!(result.is_ok());
+
+    let register_msg = ExecuteMsg::Register {
+        name: String::from("simpletest"),
+    };
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &register_msg,
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+    assert!(result.is_ok());
+
+    let current_time = get_block_time(&mut app);
+    let subdomain_msg = ExecuteMsg::RegisterSubdomain {
+        domain: String::from("simpletest"),
+        subdomain: String::from("member"),
+        new_resolver: buyer.clone(),
+        new_owner: buyer.clone(),
+        expiration: current_time + 86400,
+    };
+    let price = vec![Coin {
+        denom: String::from(DENOM),
+        amount: Uint128::from(1000u128),
+    }];
+
+    // subdomains can't be bought until the domain owner puts them up for sale
+    assert!(app
+        .execute_contract(buyer.clone(), name_service.clone(), &subdomain_msg, &price)
+        .is_err());
+
+    let sale = SubdomainSale {
+        price: Uint128::from(1000u128),
+        denom: String::from(DENOM),
+        max_duration: 43200,
+    };
+    let sale_msg = ExecuteMsg::SetSubdomainSale {
+        domain: String::from("simpletest"),
+        sale: Some(sale.clone()),
+    };
+    // only the domain owner can sell its subdomains
+    assert!(app
+        .execute_contract(buyer.clone(), name_service.clone(), &sale_msg, &[])
+        .is_err());
+    // sales need a denom
+    assert!(app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::SetSubdomainSale {
+                domain: String::from("simpletest"),
+                sale: Some(SubdomainSale {
+                    denom: String::new(),
+                    ..sale.clone()
+                }),
+            },
+            &[],
+        )
+        .is_err());
+    // and can't last longer than the longest registration
+    assert!(app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::SetSubdomainSale {
+                domain: String::from("simpletest"),
+                sale: Some(SubdomainSale {
+                    max_duration: u64::MAX,
+                    ..sale.clone()
+                }),
+            },
+            &[],
+        )
+        .is_err());
+    let result = app.execute_contract(name_owner.clone(), name_service.clone(), &sale_msg, &[]);
+    assert!(result.is_ok());
+
+    let sale_query: SubdomainSaleResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::SubdomainSale {
+            domain: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(sale_query.sale, Some(sale));
+
+    // buyer must pay the exact sale price
+    assert!(app
+        .execute_contract(
+            buyer.clone(),
+            name_service.clone(),
+            &subdomain_msg,
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(500u128),
+            }],
+        )
+        .is_err());
+    let result = app.execute_contract(buyer.clone(), name_service.clone(), &subdomain_msg, &price);
+    assert!(result.is_ok());
+
+    let owner_query: Cw721QueryMsg<Extension> = Cw721QueryMsg::OwnerOf {
+        token_id: String::from("member.simpletest.arch"),
+        include_expired: None,
+    };
+    let nft_owner: OwnerOfResponse = query(&mut app, nft.clone(), owner_query).unwrap();
+    assert_eq!(nft_owner.owner, buyer);
+
+    // subdomain lifetime is capped by the sale's max duration
+    let expiration: RecordExpirationResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::RecordExpiration {
+            name: String::from("member.simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(expiration.expiration, current_time + 43200);
+
+    // domain owner is paid the price minus the 10% protocol fee
+    let owner_balance = app.wrap().query_balance(&name_owner, DENOM).unwrap();
+    assert_eq!(owner_balance.amount, Uint128::from(5900u128));
+    let registry_balance = app.wrap().query_balance(&name_service, DENOM).unwrap();
+    assert_eq!(registry_balance.amount, Uint128::from(5100u128));
+
+    // an active subdomain can't be bought again
+    assert!(app
+        .execute_contract(buyer.clone(), name_service.clone(), &subdomain_msg, &price)
+        .is_err());
+
+    // domain owner can take subdomains off sale
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::SetSubdomainSale {
+            domain: String::from("simpletest"),
+            sale: None,
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    let sale_query: SubdomainSaleResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::SubdomainSale {
+            domain: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(sale_query.sale, None);
+
+    // sales in other denoms keep the protocol fee in that denom
+    mint_native(
+        &mut app,
+        buyer.to_string(),
+        String::from("uatom"),
+        Uint128::from(1000u128),
+    );
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::SetSubdomainSale {
+            domain: String::from("simpletest"),
+            sale: Some(SubdomainSale {
+                price: Uint128::from(1000u128),
+                denom: String::from("uatom"),
+                max_duration: 43200,
+            }),
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    let result = app.execute_contract(
+        buyer.clone(),
+        name_service.clone(),
+        &ExecuteMsg::RegisterSubdomain {
+            domain: String::from("simpletest"),
+            subdomain: String::from("guest"),
+            new_resolver: buyer.clone(),
+            new_owner: buyer.clone(),
+            expiration: current_time + 86400,
+        },
+        &[Coin {
+            denom: String::from("uatom"),
+            amount: Uint128::from(1000u128),
+        }],
+    );
+    assert!(result.is_ok());
+    let owner_balance = app.wrap().query_balance(&name_owner, "uatom").unwrap();
+    assert_eq!(owner_balance.amount, Uint128::from(900u128));
+    let registry_balance = app.wrap().query_balance(&name_service, "uatom").unwrap();
+    assert_eq!(registry_balance.amount, Uint128::from(100u128));
+
+    // and withdrawn in it
+    let withdraw = |amount: u128| ExecuteMsg::Withdraw {
+        amount: Uint128::from(amount),
+        denom: Some(String::from("uatom")),
+    };
+    assert!(app
+        .execute_contract(owner.clone(), name_service.clone(), &withdraw(101), &[])
+        .is_err());
+    let result = app.execute_contract(owner.clone(), name_service.clone(), &withdraw(100), &[]);
+    assert!(result.is_ok());
+    let wallet_balance = app.wrap().query_balance(&wallet, "uatom").unwrap();
+    assert_eq!(wallet_balance.amount, Uint128::from(100u128));
+}
+
+#[test]
+fn test_subdomain_fuses() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    let name_owner2 = Addr::unchecked("mintothernames");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config,
+    };
+    let _config_update =
+        app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+
+    let register_msg = ExecuteMsg::Register {
+        name: String::from("simpletest"),
+    };
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &register_msg,
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+    assert!(result.is_ok());
+
+    // domain owner keeps the subdomain nft, so could normally remove it at will
+    let current_time = get_block_time(&mut app);
+    let subdomain_msg = ExecuteMsg::RegisterSubdomain {
+        domain: String::from("simpletest"),
+        subdomain: String::from("subdomain"),
+        new_resolver: name_owner2.clone(),
+        new_owner: name_owner.clone(),
+        expiration: current_time + 43200,
+    };
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &subdomain_msg,
+        &[],
+    );
+    assert!(result.is_ok());
+
+    let burn_msg = ExecuteMsg::BurnSubdomainFuses {
+        domain: String::from("simpletest"),
+        subdomain: String::from("subdomain"),
+        fuses: CANNOT_REMOVE,
+    };
+    // only the domain owner can burn fuses
+    assert!(app
+        .execute_contract(name_owner2.clone(), name_service.clone(), &burn_msg, &[])
+        .is_err());
+    // unknown and unused fuses are rejected
+    for fuses in [1 << 1, 1 << 3, 1 << 10] {
+        assert!(app
+            .execute_contract(
+                name_owner.clone(),
+                name_service.clone(),
+                &ExecuteMsg::BurnSubdomainFuses {
+                    domain: String::from("simpletest"),
+                    subdomain: String::from("subdomain"),
+                    fuses,
+                },
+                &[],
+            )
+            .is_err());
+    }
+    let result = app.execute_contract(name_owner.clone(), name_service.clone(), &burn_msg, &[]);
+    assert!(result.is_ok());
+
+    let fuses: SubdomainFusesResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::SubdomainFuses {
+            name: String::from("subdomain.simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(fuses.fuses, CANNOT_REMOVE);
+
+    // CANNOT_REMOVE blocks removing the subdomain
+    let remove_subdomain_msg = ExecuteMsg::RemoveSubdomain {
+        domain: String::from("simpletest"),
+        subdomain: String::from("subdomain"),
+    };
+    assert!(app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &remove_subdomain_msg,
+            &[],
+        )
+        .is_err());
+
+    // PARENT_CANNOT_CONTROL freezes the burned fuses
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::BurnSubdomainFuses {
+            domain: String::from("simpletest"),
+            subdomain: String::from("subdomain"),
+            fuses: PARENT_CANNOT_CONTROL,
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    assert!(app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::BurnSubdomainFuses {
+                domain: String::from("simpletest"),
+                subdomain: String::from("subdomain"),
+                fuses: CANNOT_REMOVE,
+            },
+            &[],
+        )
+        .is_err());
+
+    // expiration can still be extended
+    let extend_msg = ExecuteMsg::ExtendSubdomainExpiry {
+        domain: String::from("simpletest"),
+        subdomain: String::from("subdomain"),
+        expiration: current_time + 50000,
+    };
+    let result = app.execute_contract(name_owner.clone(), name_service.clone(), &extend_msg, &[]);
+    assert!(result.is_ok());
+
+    // fuses lapse once the subdomain expires
+    increment_block_time(&mut app, current_time + 50001, 77);
+    let fuses: SubdomainFusesResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::SubdomainFuses {
+            name: String::from("subdomain.simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(fuses.fuses, 0);
+    assert_eq!(fuses.expiration, current_time + 50000);
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &remove_subdomain_msg,
+        &[],
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_batch_register_and_renew() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    let name_owner2 = Addr::unchecked("mintothernames");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(100000u128),
+    );
+    mint_native(
+        &mut app,
+        name_owner2.to_string(),
+        String::from(DENOM),
+        Uint128::from(100000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let base_expiration: u64 = 86400;
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config,
+    };
+    let _config_update =
+        app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+
+    let names = vec![
+        String::from("alpha"),
+        String::from("bravo"),
+        String::from("charlie"),
+    ];
+    let batch_register_msg = ExecuteMsg::BatchRegister {
+        names: names.clone(),
+    };
+    // payment must cover one base interval for every name
+    assert!(app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &batch_register_msg,
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(10000u128),
+            }],
+        )
+        .is_err());
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &batch_register_msg,
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(15000u128),
+        }],
+    );
+    assert!(result.is_ok());
+
+    let current_time = get_block_time(&mut app);
+    for name in names.iter() {
+        let owner_query: Cw721QueryMsg<Extension> = Cw721QueryMsg::OwnerOf {
+            token_id: format!("{}.arch", name),
+            include_expired: None,
+        };
+        let nft_owner: OwnerOfResponse = query(&mut app, nft.clone(), owner_query).unwrap();
+        assert_eq!(nft_owner.owner, name_owner);
+        let resolve: ResolveRecordResponse = query(
+            &mut app,
+            name_service.clone(),
+            QueryMsg::ResolveRecord {
+                name: format!("{}.arch", name),
+            },
+        )
+        .unwrap();
+        assert_eq!(resolve.expiration, current_time + base_expiration);
+    }
+
+    // an invalid or repeated name fails the whole batch
+    let bad_batches = vec![
+        vec![String::from("delta"), String::from("x")],
+        vec![String::from("delta"), String::from("delta")],
+        vec![String::from("delta"), String::from("alpha")],
+        vec![],
+    ];
+    for bad_names in bad_batches {
+        assert!(app
+            .execute_contract(
+                name_owner.clone(),
+                name_service.clone(),
+                &ExecuteMsg::BatchRegister { names: bad_names },
+                &[Coin {
+                    denom: String::from(DENOM),
+                    amount: Uint128::from(10000u128),
+                }],
+            )
+            .is_err());
+    }
+    let total: NumTokensResponse = query(
+        &mut app,
+        nft.clone(),
+        Cw721QueryMsg::<Extension>::NumTokens {},
+    )
+    .unwrap();
+    assert_eq!(total.count, 3);
+
+    let batch_renew_msg = ExecuteMsg::BatchRenew {
+        names: vec![String::from("alpha"), String::from("bravo")],
+    };
+    // only the owner of every name can renew the batch
+    assert!(app
+        .execute_contract(
+            name_owner2.clone(),
+            name_service.clone(),
+            &batch_renew_msg,
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(10000u128),
+            }],
+        )
+        .is_err());
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &batch_renew_msg,
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(10000u128),
+        }],
+    );
+    assert!(result.is_ok());
+
+    for (name, expiration) in [
+        ("alpha.arch", current_time + base_expiration * 2),
+        ("bravo.arch", current_time + base_expiration * 2),
+        ("charlie.arch", current_time + base_expiration),
+    ] {
+        let resolve: ResolveRecordResponse = query(
+            &mut app,
+            name_service.clone(),
+            QueryMsg::ResolveRecord {
+                name: String::from(name),
+            },
+        )
+        .unwrap();
+        assert_eq!(resolve.expiration, expiration);
+    }
+}
+
+#[test]
+fn test_third_party_renewals() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    // payer funds renewals of a name it doesn't own
+    let payer = Addr::unchecked("payer");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    mint_native(
+        &mut app,
+        payer.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let base_cost = Uint128::from(5000u64);
+    let base_expiration: u64 = 86400;
+    let mut update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost,
+        base_expiration,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config.clone(),
+    };
+    let _config_update =
+        app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+
+    let register_msg = ExecuteMsg::Register {
+        name: String::from("simpletest"),
+    };
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &register_msg,
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: base_cost,
+        }],
+    );
+    assert!(result.is_ok());
+    let current_time = get_block_time(&mut app);
+
+    let renew_registration_msg = ExecuteMsg::RenewRegistration {
+        name: String::from("simpletest"),
+    };
+    let payment = [Coin {
+        denom: String::from(DENOM),
+        amount: base_cost,
+    }];
+    // third party renewals are disabled by default
+    assert!(app
+        .execute_contract(
+            payer.clone(),
+            name_service.clone(),
+            &renew_registration_msg,
+            &payment,
+        )
+        .is_err());
+
+    update_config.allow_third_party_renewals = true;
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config,
+    };
+    let result = app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+    assert!(result.is_ok());
+
+    let result = app
+        .execute_contract(
+            payer.clone(),
+            name_service.clone(),
+            &renew_registration_msg,
+            &payment,
+        )
+        .unwrap();
+    // payer is recorded in the response attributes
+    assert!(result.events.iter().any(|event| event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "payer" && attr.value == payer.as_str())));
+
+    let name_resolution: ResolveRecordResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::ResolveRecord {
+            name: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        name_resolution.expiration,
+        current_time + base_expiration * 2
+    );
+    // renewing doesn't change ownership or resolution
+    assert_eq!(name_resolution.address.unwrap(), name_owner);
+    let owner_query: Cw721QueryMsg<Extension> = Cw721QueryMsg::OwnerOf {
+        token_id: String::from("simpletest.arch"),
+        include_expired: None,
+    };
+    let nft_owner: OwnerOfResponse = query(&mut app, nft.clone(), owner_query).unwrap();
+    assert_eq!(nft_owner.owner, name_owner);
+}
+
+#[test]
+fn test_auto_renewals() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    let name_owner2 = Addr::unchecked("mintothernames");
+    // keeper processes renewals for the keeper fee
+    let keeper = Addr::unchecked("keeper");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(100000u128),
+    );
+    mint_native(
+        &mut app,
+        name_owner2.to_string(),
+        String::from(DENOM),
+        Uint128::from(100000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let base_expiration: u64 = 86400;
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::from(100u64),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    // renewed names must leave the renewal window
+    assert!(app
+        .execute_contract(
+            owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: Config {
+                    renewal_window: base_expiration,
+                    ..update_config.clone()
+                },
+            },
+            &[],
+        )
+        .is_err());
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config.clone(),
+    };
+    let _config_update =
+        app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+
+    let register_msg = ExecuteMsg::Register {
+        name: String::from("simpletest"),
+    };
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &register_msg,
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+    assert!(result.is_ok());
+    let current_time = get_block_time(&mut app);
+
+    // escrow covers two renewals of base_cost + keeper_fee
+    let deposit_msg = ExecuteMsg::DepositRenewalFunds {
+        name: String::from("simpletest"),
+    };
+    let deposit = [Coin {
+        denom: String::from(DENOM),
+        amount: Uint128::from(10200u128),
+    }];
+    assert!(app
+        .execute_contract(
+            name_owner2.clone(),
+            name_service.clone(),
+            &deposit_msg,
+            &deposit
+        )
+        .is_err());
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &deposit_msg,
+        &deposit,
+    );
+    assert!(result.is_ok());
+    let funds: RenewalFundsResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::RenewalFunds {
+            name: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(funds.balance, Uint128::from(10200u128));
+
+    // names are only renewed once they're within the renewal window
+    let process_msg = ExecuteMsg::ProcessRenewals {
+        start_after: None,
+        limit: None,
+    };
+    let result = app.execute_contract(keeper.clone(), name_service.clone(), &process_msg, &[]);
+    assert!(result.is_ok());
+    let resolve: ResolveRecordResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::ResolveRecord {
+            name: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(resolve.expiration, current_time + base_expiration);
+
+    increment_block_time(&mut app, current_time + 50000, 77);
+    let result = app
+        .execute_contract(keeper.clone(), name_service.clone(), &process_msg, &[])
+        .unwrap();
+    // the renewal is credited to the owner whose escrow paid it, not the keeper,
+    // at the amount taken from the escrow
+    let event = result
+        .events
+        .iter()
+        .find(|e| e.ty == format!("wasm-{}", RENEW_EVENT))
+        .unwrap();
+    let renew = RenewEvent::try_from(event).unwrap();
+    assert_eq!(renew.payer, name_owner);
+    assert_eq!(renew.cost, Uint128::from(5100u128));
+    let resolve: ResolveRecordResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::ResolveRecord {
+            name: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(resolve.expiration, current_time + base_expiration * 2);
+
+    // the renewed name has left the window, so processing again
+    // in the same block renews and charges nothing
+    let result = app.execute_contract(keeper.clone(), name_service.clone(), &process_msg, &[]);
+    assert!(result.is_ok());
+    let resolve: ResolveRecordResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::ResolveRecord {
+            name: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(resolve.expiration, current_time + base_expiration * 2);
+    let keeper_balance = app.wrap().query_balance(&keeper, DENOM).unwrap();
+    assert_eq!(keeper_balance.amount, Uint128::from(100u128));
+    let funds: RenewalFundsResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::RenewalFunds {
+            name: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(funds.balance, Uint128::from(5100u128));
+
+    // names a full interval would take past the max_intervals cap are skipped
+    let result = app.execute_contract(
+        owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::UpdateConfig {
+            config: Config {
+                name_rules: NamingRules {
+                    max_intervals: 1,
+                    ..NamingRules::default()
+                },
+                ..update_config
+            },
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    increment_block_time(&mut app, current_time + base_expiration * 2 - 40000, 77);
+    let result = app.execute_contract(keeper.clone(), name_service.clone(), &process_msg, &[]);
+    assert!(result.is_ok());
+    let resolve: ResolveRecordResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::ResolveRecord {
+            name: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(resolve.expiration, current_time + base_expiration * 2);
+    let keeper_balance = app.wrap().query_balance(&keeper, DENOM).unwrap();
+    assert_eq!(keeper_balance.amount, Uint128::from(100u128));
+    let funds: RenewalFundsResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::RenewalFunds {
+            name: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(funds.balance, Uint128::from(5100u128));
+
+    // fee withdrawals can't touch escrowed funds
+    let registry_balance = app.wrap().query_balance(&name_service, DENOM).unwrap();
+    assert_eq!(registry_balance.amount, Uint128::from(15100u128));
+    assert!(app
+        .execute_contract(
+            owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::Withdraw {
+                amount: Uint128::from(10001u128),
+                denom: None,
+            },
+            &[],
+        )
+        .is_err());
+    let result = app.execute_contract(
+        owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::Withdraw {
+            amount: Uint128::from(10000u128),
+            denom: None,
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+
+    // only the name owner can withdraw, and no more than the balance
+    let withdraw_msg = ExecuteMsg::WithdrawRenewalFunds {
+        name: String::from("simpletest"),
+        amount: Uint128::from(5100u128),
+    };
+    assert!(app
+        .execute_contract(
+            name_owner2.clone(),
+            name_service.clone(),
+            &withdraw_msg,
+            &[]
+        )
+        .is_err());
+    assert!(app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::WithdrawRenewalFunds {
+                name: String::from("simpletest"),
+                amount: Uint128::from(5101u128),
+            },
+            &[],
+        )
+        .is_err());
+    let result = app.execute_contract(name_owner.clone(), name_service.clone(), &withdraw_msg, &[]);
+    assert!(result.is_ok());
+    let funds: RenewalFundsResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::RenewalFunds {
+            name: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(funds.balance, Uint128::zero());
+    let owner_balance = app.wrap().query_balance(&name_owner, DENOM).unwrap();
+    assert_eq!(owner_balance.amount, Uint128::from(89900u128));
+}
+
+#[test]
+fn test_expiring_between() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(100000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let base_expiration: u64 = 86400;
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config,
+    };
+    let _config_update =
+        app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+
+    // register names 100 seconds apart
+    let start_time = get_block_time(&mut app);
+    for (i, name) in ["alpha", "bravo", "charlie"].iter().enumerate() {
+        increment_block_time(&mut app, start_time + 100 * i as u64, 1);
+        let result = app.execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::Register {
+                name: String::from(*name),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        );
+        assert!(result.is_ok());
+    }
+    let expiring = |app: &mut App, from: u64, to: u64, start_after: Option<&str>, limit| {
+        let res: ExpiringBetweenResponse = query(
+            app,
+            name_service.clone(),
+            QueryMsg::ExpiringBetween {
+                from,
+                to,
+                start_after: start_after.map(String::from),
+                limit,
+            },
+        )
+        .unwrap();
+        res.names
+            .into_iter()
+            .map(|n| (n.name, n.expiration))
+            .collect::<Vec<(String, u64)>>()
+    };
+    let alpha_expiration = start_time + base_expiration;
+
+    assert_eq!(
+        expiring(
+            &mut app,
+            alpha_expiration,
+            alpha_expiration + 100,
+            None,
+            None
+        ),
+        vec![
+            (String::from("alpha.arch"), alpha_expiration),
+            (String::from("bravo.arch"), alpha_expiration + 100),
+        ]
+    );
+    // paging
+    assert_eq!(
+        expiring(&mut app, 0, u64::MAX, None, Some(1)),
+        vec![(String::from("alpha.arch"), alpha_expiration)]
+    );
+    assert_eq!(
+        expiring(&mut app, 0, u64::MAX, Some("alpha.arch"), Some(1)),
+        vec![(String::from("bravo.arch"), alpha_expiration + 100)]
+    );
+    // cursors are normalized, and an unknown one starts over
+    assert_eq!(
+        expiring(&mut app, 0, u64::MAX, Some("Alpha"), Some(1)),
+        vec![(String::from("bravo.arch"), alpha_expiration + 100)]
+    );
+    assert_eq!(
+        expiring(&mut app, 0, u64::MAX, Some("unknown"), Some(1)),
+        vec![(String::from("alpha.arch"), alpha_expiration)]
+    );
+
+    // renewing moves a name in the index
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::RenewRegistration {
+            name: String::from("alpha"),
+        },
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+    assert!(result.is_ok());
+    assert_eq!(
+        expiring(&mut app, 0, alpha_expiration + 1000, None, None),
+        vec![
+            (String::from("bravo.arch"), alpha_expiration + 100),
+            (String::from("charlie.arch"), alpha_expiration + 200),
+        ]
+    );
+    assert_eq!(
+        expiring(&mut app, alpha_expiration + 1000, u64::MAX, None, None),
+        vec![(
+            String::from("alpha.arch"),
+            alpha_expiration + base_expiration
+        )]
+    );
+
+    // subdomains are indexed through registration, extension and removal
+    let current_time = get_block_time(&mut app);
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::RegisterSubdomain {
+            domain: String::from("charlie"),
+            subdomain: String::from("subdomain"),
+            new_resolver: name_owner.clone(),
+            new_owner: name_owner.clone(),
+            expiration: current_time + 1000,
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    assert_eq!(
+        expiring(&mut app, 0, current_time + 2000, None, None),
+        vec![(String::from("subdomain.charlie.arch"), current_time + 1000)]
+    );
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::ExtendSubdomainExpiry {
+            domain: String::from("charlie"),
+            subdomain: String::from("subdomain"),
+            expiration: current_time + 3000,
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    assert_eq!(
+        expiring(&mut app, 0, current_time + 2000, None, None),
+        vec![]
+    );
+    assert_eq!(
+        expiring(&mut app, 0, current_time + 3000, None, None),
+        vec![(String::from("subdomain.charlie.arch"), current_time + 3000)]
+    );
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::RemoveSubdomain {
+            domain: String::from("charlie"),
+            subdomain: String::from("subdomain"),
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    assert_eq!(
+        expiring(&mut app, 0, current_time + 3000, None, None),
+        vec![]
+    );
+}
+
+#[test]
+fn test_subdomains_query() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    let name_owner2 = Addr::unchecked("mintothernames");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config,
+    };
+    let _config_update =
+        app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+
+    for name in ["simpletest", "other"] {
+        let result = app.execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::Register {
+                name: String::from(name),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        );
+        assert!(result.is_ok());
+    }
+
+    let current_time = get_block_time(&mut app);
+    for (subdomain, new_owner, expiration) in [
+        ("alpha", name_owner2.clone(), current_time + 1000),
+        ("bravo", name_owner.clone(), current_time + 50000),
+        ("charlie", name_owner.clone(), current_time + 50000),
+    ] {
+        let result = app.execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("simpletest"),
+                subdomain: String::from(subdomain),
+                new_resolver: new_owner.clone(),
+                new_owner,
+                expiration,
+            },
+            &[],
+        );
+        assert!(result.is_ok());
+    }
+
+    let subdomains = |app: &mut App,
+                      domain: &str,
+                      start_after: Option<&str>,
+                      limit: Option<u32>,
+                      include_expired: Option<bool>| {
+        let res: SubdomainsResponse = query(
+            app,
+            name_service.clone(),
+            QueryMsg::Subdomains {
+                domain: String::from(domain),
+                start_after: start_after.map(String::from),
+                limit,
+                include_expired,
+            },
+        )
+        .unwrap();
+        res.subdomains
+    };
+
+    let res = subdomains(&mut app, "simpletest.arch", None, None, None);
+    assert_eq!(res.len(), 3);
+    assert_eq!(res[0].name, "alpha.simpletest.arch");
+    assert_eq!(res[0].owner, name_owner2.to_string());
+    assert_eq!(res[0].record.resolver, name_owner2);
+    assert_eq!(res[0].record.expiration, current_time + 1000);
+    assert_eq!(res[0].status, SubDomainStatus::ExistingMintActive);
+    assert_eq!(res[1].owner, name_owner.to_string());
+    assert!(subdomains(&mut app, "other.arch", None, None, None).is_empty());
+
+    // paging by subdomain label
+    let res = subdomains(&mut app, "simpletest.arch", Some("alpha"), Some(1), None);
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].name, "bravo.simpletest.arch");
+
+    // expired subdomains are skipped unless requested
+    increment_block_time(&mut app, current_time + 2000, 100);
+    let res = subdomains(&mut app, "simpletest.arch", None, None, None);
+    assert_eq!(
+        res.iter().map(|s| s.name.as_str()).collect::<Vec<&str>>(),
+        vec!["bravo.simpletest.arch", "charlie.simpletest.arch"]
+    );
+    let res = subdomains(&mut app, "simpletest.arch", None, None, Some(true));
+    assert_eq!(res.len(), 3);
+    assert_eq!(res[0].status, SubDomainStatus::ExistingMintExpired);
+    // pages continue after the last label read, none is left after charlie
+    let page = |app: &mut App, start_after: Option<&str>| {
+        let res: SubdomainsResponse = query(
+            app,
+            name_service.clone(),
+            QueryMsg::Subdomains {
+                domain: String::from("simpletest.arch"),
+                start_after: start_after.map(String::from),
+                limit: Some(1),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+        res
+    };
+    let res = page(&mut app, None);
+    assert_eq!(res.subdomains[0].name, "bravo.simpletest.arch");
+    assert_eq!(res.last_scanned, Some(String::from("bravo")));
+    let res = page(&mut app, Some("bravo"));
+    assert_eq!(res.subdomains[0].name, "charlie.simpletest.arch");
+    assert_eq!(res.last_scanned, None);
+
+    // removed subdomains leave the listing
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::RemoveSubdomain {
+            domain: String::from("simpletest"),
+            subdomain: String::from("charlie"),
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    let res = subdomains(&mut app, "simpletest.arch", None, None, Some(true));
+    assert_eq!(
+        res.iter().map(|s| s.name.as_str()).collect::<Vec<&str>>(),
+        vec!["alpha.simpletest.arch", "bravo.simpletest.arch"]
+    );
+}
+
+#[test]
+fn test_name_info() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    let name_owner2 = Addr::unchecked("mintothernames");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    mint_native(
+        &mut app,
+        name_owner2.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let base_expiration = 86400;
+    let grace_period = 10000;
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config,
+    };
+    let _config_update =
+        app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+
+    let start_time = get_block_time(&mut app);
+    let register_msg = ExecuteMsg::Register {
+        name: String::from("simpletest"),
+    };
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &register_msg,
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+    assert!(result.is_ok());
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::RegisterSubdomain {
+            domain: String::from("simpletest"),
+            subdomain: String::from("subdomain"),
+            new_resolver: name_owner2.clone(),
+            new_owner: name_owner2.clone(),
+            expiration: start_time + 1000,
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+
+    let info: NameInfoResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::NameInfo {
+            name: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(info.resolver, name_owner);
+    assert_eq!(info.owner, name_owner.to_string());
+    assert_eq!(info.created, start_time);
+    assert_eq!(info.expiration, start_time + base_expiration);
+    assert_eq!(info.status, NameStatus::Active);
+    assert_eq!(info.metadata.expiry, Some(start_time + base_expiration));
+    assert_eq!(info.subdomain_count, 1);
+    assert_eq!(info.parent, None);
+
+    let info: NameInfoResponse = query(
+        &mut app,
+        name_service.clone(),
+        QueryMsg::NameInfo {
+            name: String::from("subdomain.simpletest.arch"),
+        },
+    )
+    .unwrap();
+    assert_eq!(info.owner, name_owner2.to_string());
+    assert_eq!(info.subdomain_count, 0);
+    assert_eq!(info.parent, Some(String::from("simpletest.arch")));
+
+    // during the grace period only the owner can bring the name back
+    increment_block_time(&mut app, start_time + base_expiration + 1, 100);
+    let name_info = |app: &mut App| -> NameInfoResponse {
+        query(
+            app,
+            name_service.clone(),
+            QueryMsg::NameInfo {
+                name: String::from("simpletest.arch"),
+            },
+        )
+        .unwrap()
+    };
+    assert_eq!(name_info(&mut app).status, NameStatus::InGrace);
+    assert!(app
+        .execute_contract(
+            name_owner2.clone(),
+            name_service.clone(),
+            &register_msg,
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        )
+        .is_err());
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::RenewRegistration {
+            name: String::from("simpletest"),
+        },
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+    assert!(result.is_ok());
+    let info = name_info(&mut app);
+    assert_eq!(info.status, NameStatus::Active);
+    assert_eq!(info.expiration, start_time + base_expiration * 2);
+
+    // once the grace period ends the name is up for grabs
+    increment_block_time(
+        &mut app,
+        start_time + base_expiration * 2 + grace_period + 1,
+        100,
+    );
+    assert_eq!(name_info(&mut app).status, NameStatus::Expired);
+    let result = app.execute_contract(
+        name_owner2.clone(),
+        name_service.clone(),
+        &register_msg,
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+    assert!(result.is_ok());
+    assert_eq!(name_info(&mut app).owner, name_owner2.to_string());
+}
+
+#[test]
+fn test_sync_ownership() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    let buyer = Addr::unchecked("buyer");
+    let keeper = Addr::unchecked("keeper");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config,
+    };
+    let _config_update =
+        app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::Register {
+            name: String::from("simpletest"),
+        },
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+    assert!(result.is_ok());
+    let current_time = get_block_time(&mut app);
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::RegisterSubdomain {
+            domain: String::from("simpletest"),
+            subdomain: String::from("dapp"),
+            new_resolver: name_owner.clone(),
+            new_owner: name_owner.clone(),
+            expiration: current_time + 43200,
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::UpdateUserDomainData {
+            name: String::from("simpletest"),
+            metadata_update: MetaDataUpdateMsg {
+                description: Some(String::from("my domain")),
+                image: Some(String::from("ipfs://image")),
+                accounts: Some(vec![Account {
+                    username: Some(String::from("seller")),
+                    profile: None,
+                    account_type: Some(String::from("twitter")),
+                    verfication_hash: None,
+                }]),
+                websites: Some(vec![]),
+            },
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+
+    let sync_msg = ExecuteMsg::SyncOwnership {
+        name: String::from("simpletest"),
+    };
+    // nothing to sync before a transfer
+    assert!(app
+        .execute_contract(keeper.clone(), name_service.clone(), &sync_msg, &[])
+        .is_err());
+
+    for token_id in ["simpletest.arch", "dapp.simpletest.arch"] {
+        let result = app.execute_contract(
+            name_owner.clone(),
+            nft.clone(),
+            &Cw721ExecuteMsg::TransferNft {
+                recipient: buyer.to_string(),
+                token_id: String::from(token_id),
+            },
+            &[],
+        );
+        assert!(result.is_ok());
+    }
+    let resolve = |app: &mut App, name: &str| -> Option<String> {
+        let res: ResolveRecordResponse = query(
+            app,
+            name_service.clone(),
+            QueryMsg::ResolveRecord {
+                name: String::from(name),
+            },
+        )
+        .unwrap();
+        res.address
+    };
+    // the seller still receives funds sent to the name until it is synced
+    assert_eq!(
+        resolve(&mut app, "simpletest.arch"),
+        Some(name_owner.to_string())
+    );
+
+    // anyone can sync a transferred name
+    let result = app.execute_contract(keeper.clone(), name_service.clone(), &sync_msg, &[]);
+    assert!(result.is_ok());
+    assert_eq!(
+        resolve(&mut app, "simpletest.arch"),
+        Some(buyer.to_string())
+    );
+    let info: NftInfoResponse<Extension> = query(
+        &mut app,
+        nft.clone(),
+        Cw721QueryMsg::<Extension>::NftInfo {
+            token_id: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    let metadata = info.extension.unwrap();
+    assert_eq!(metadata.image, None);
+    assert_eq!(metadata.accounts, Some(vec![]));
+    assert!(app
+        .execute_contract(keeper.clone(), name_service.clone(), &sync_msg, &[])
+        .is_err());
+
+    // subdomains sync the same way and update their parent's metadata
+    let result = app.execute_contract(
+        keeper.clone(),
+        name_service.clone(),
+        &ExecuteMsg::SyncOwnership {
+            name: String::from("dapp.simpletest"),
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    assert_eq!(
+        resolve(&mut app, "dapp.simpletest.arch"),
+        Some(buyer.to_string())
+    );
+    let info: NftInfoResponse<Extension> = query(
+        &mut app,
+        nft.clone(),
+        Cw721QueryMsg::<Extension>::NftInfo {
+            token_id: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    let subdomains = info.extension.unwrap().subdomains.unwrap();
+    assert_eq!(subdomains[0].resolver, Some(buyer.clone()));
+}
+
+#[test]
+fn test_verify_and_reconcile() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    let name_owner2 = Addr::unchecked("mintothernames");
+    let buyer = Addr::unchecked("buyer");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config,
+    };
+    let _config_update =
+        app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::Register {
+            name: String::from("simpletest"),
+        },
+        &[Coin {
+            denom: String::from(DENOM),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+    assert!(result.is_ok());
+    let current_time = get_block_time(&mut app);
+    for (subdomain, new_owner) in [("dapp", name_owner.clone()), ("other", name_owner2.clone())] {
+        let result = app.execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("simpletest"),
+                subdomain: String::from(subdomain),
+                new_resolver: new_owner.clone(),
+                new_owner,
+                expiration: current_time + 43200,
+            },
+            &[],
+        );
+        assert!(result.is_ok());
+    }
+
+    let verify = |app: &mut App, start_after: Option<&str>, limit: Option<u32>| {
+        let res: VerifyConsistencyResponse = query(
+            app,
+            name_service.clone(),
+            QueryMsg::VerifyConsistency {
+                start_after: start_after.map(String::from),
+                limit,
+            },
+        )
+        .unwrap();
+        res
+    };
+    let res = verify(&mut app, None, None);
+    assert_eq!(res.mismatches, vec![]);
+    assert_eq!(res.last_checked, Some(String::from("simpletest.arch")));
+
+    // an active subdomain held by someone else can't be removed by the domain owner
+    assert!(app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::RemoveSubdomain {
+                domain: String::from("simpletest"),
+                subdomain: String::from("other"),
+            },
+            &[],
+        )
+        .is_err());
+
+    // drift the token state away from the registry
+    let info: NftInfoResponse<Extension> = query(
+        &mut app,
+        nft.clone(),
+        Cw721QueryMsg::<Extension>::NftInfo {
+            token_id: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    let mut metadata = info.extension.unwrap();
+    let expiration = metadata.expiry.unwrap();
+    metadata.expiry = Some(1);
+    metadata.subdomains = Some(vec![Subdomain {
+        name: Some(String::from("ghost")),
+        resolver: Some(name_owner.clone()),
+        minted: None,
+        created: Some(current_time),
+        expiry: Some(current_time + 43200),
+    }]);
+    let result = app.execute_contract(
+        name_service.clone(),
+        nft.clone(),
+        &Cw721ExecuteMsg::UpdateMetadata(UpdateMetadataMsg {
+            token_id: String::from("simpletest.arch"),
+            extension: Some(metadata),
+        }),
+        &[],
+    );
+    assert!(result.is_ok());
+    let result = app.execute_contract(
+        name_owner.clone(),
+        nft.clone(),
+        &Cw721ExecuteMsg::TransferNft {
+            recipient: buyer.to_string(),
+            token_id: String::from("dapp.simpletest.arch"),
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+
+    let res = verify(&mut app, None, None);
+    assert_eq!(
+        res.mismatches,
+        vec![
+            NameMismatch {
+                name: String::from("dapp.simpletest.arch"),
+                issues: vec![ConsistencyIssue::OwnerMismatch {
+                    registry: Some(name_owner.clone()),
+                    token: buyer.clone(),
+                }],
+            },
+            NameMismatch {
+                name: String::from("simpletest.arch"),
+                issues: vec![
+                    ConsistencyIssue::ExpiryMismatch {
+                        registry: expiration,
+                        token: Some(1),
+                    },
+                    ConsistencyIssue::SubdomainsMismatch {
+                        missing: vec![String::from("dapp"), String::from("other")],
+                        stale: vec![],
+                        unknown: vec![String::from("ghost")],
+                    },
+                ],
+            },
+        ]
+    );
+    // paging
+    let res = verify(&mut app, None, Some(1));
+    assert_eq!(res.mismatches.len(), 1);
+    assert_eq!(res.last_checked, Some(String::from("dapp.simpletest.arch")));
+    let res = verify(&mut app, Some("other.simpletest.arch"), None);
+    assert_eq!(res.mismatches.len(), 1);
+    assert_eq!(res.mismatches[0].name, "simpletest.arch");
+
+    // only the admin can reconcile
+    let reconcile_msg = ExecuteMsg::Reconcile {
+        names: vec![String::from("simpletest")],
+    };
+    assert!(app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &reconcile_msg,
+            &[]
+        )
+        .is_err());
+    let result = app.execute_contract(owner.clone(), name_service.clone(), &reconcile_msg, &[]);
+    assert!(result.is_ok());
+
+    // ownership drift is left for SyncOwnership
+    let res = verify(&mut app, None, None);
+    assert_eq!(res.mismatches.len(), 1);
+    assert_eq!(res.mismatches[0].name, "dapp.simpletest.arch");
+    let info: NftInfoResponse<Extension> = query(
+        &mut app,
+        nft.clone(),
+        Cw721QueryMsg::<Extension>::NftInfo {
+            token_id: String::from("simpletest.arch"),
+        },
+    )
+    .unwrap();
+    let metadata = info.extension.unwrap();
+    assert_eq!(metadata.expiry, Some(expiration));
+    assert_eq!(
+        metadata
+            .subdomains
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name.unwrap())
+            .collect::<Vec<String>>(),
+        vec![String::from("dapp"), String::from("other")]
+    );
+}
+
+#[test]
+fn test_dispute_resolution() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    let name_owner2 = Addr::unchecked("mintothernames");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let mut update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config.clone(),
+    };
+    let _config_update =
+        app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[]);
+
+    for name in ["simpletest", "stolen"] {
+        let result = app.execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::Register {
+                name: String::from(name),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        );
+        assert!(result.is_ok());
+    }
+    let current_time = get_block_time(&mut app);
+    let result = app.execute_contract(
+        name_owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::RegisterSubdomain {
+            domain: String::from("simpletest"),
+            subdomain: String::from("dapp"),
+            new_resolver: name_owner2.clone(),
+            new_owner: name_owner2.clone(),
+            expiration: current_time + 43200,
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+
+    // only the admin can revoke
+    let revoke_msg = ExecuteMsg::RevokeName {
+        name: String::from("simpletest"),
+        reason: String::from("trademark"),
+    };
+    assert!(app
+        .execute_contract(name_owner.clone(), name_service.clone(), &revoke_msg, &[])
+        .is_err());
+    let result = app
+        .execute_contract(owner.clone(), name_service.clone(), &revoke_msg, &[])
+        .unwrap();
+    assert!(result.events.iter().any(|event| event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "reason" && attr.value == "trademark")));
+    for name in ["simpletest.arch", "dapp.simpletest.arch"] {
+        let res: Result<NameInfoResponse, StdError> = query(
+            &mut app,
+            name_service.clone(),
+            QueryMsg::NameInfo {
+                name: String::from(name),
+            },
+        );
+        assert!(res.is_err());
+    }
+    let tokens: NumTokensResponse = query(
+        &mut app,
+        nft.clone(),
+        Cw721QueryMsg::<Extension>::NumTokens {},
+    )
+    .unwrap();
+    assert_eq!(tokens.count, 1);
+
+    // reassigning keeps the expiration
+    let name_info = |app: &mut App| -> NameInfoResponse {
+        query(
+            app,
+            name_service.clone(),
+            QueryMsg::NameInfo {
+                name: String::from("stolen.arch"),
+            },
+        )
+        .unwrap()
+    };
+    let before = name_info(&mut app);
+    let result = app.execute_contract(
+        owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::ReassignName {
+            name: String::from("stolen"),
+            new_owner: name_owner2.clone(),
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    let after = name_info(&mut app);
+    assert_eq!(after.owner, name_owner2.to_string());
+    assert_eq!(after.resolver, name_owner2);
+    assert_eq!(after.expiration, before.expiration);
+
+    // with a timelock disputes wait and can be cancelled
+    update_config.dispute_timelock = 1000;
+    let result = app.execute_contract(
+        owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::UpdateConfig {
+            config: update_config.clone(),
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    let pending = |app: &mut App| -> Option<PendingDispute> {
+        let res: PendingDisputeResponse = query(
+            app,
+            name_service.clone(),
+            QueryMsg::PendingDispute {
+                name: String::from("stolen.arch"),
+            },
+        )
+        .unwrap();
+        res.dispute
+    };
+    let revoke_msg = ExecuteMsg::RevokeName {
+        name: String::from("stolen"),
+        reason: String::from("mistake"),
+    };
+    let result = app.execute_contract(owner.clone(), name_service.clone(), &revoke_msg, &[]);
+    assert!(result.is_ok());
+    assert_eq!(
+        pending(&mut app),
+        Some(PendingDispute {
+            action: DisputeAction::Revoke {
+                reason: String::from("mistake"),
+            },
+            executable_at: current_time + 1000,
+        })
+    );
+    assert!(app
+        .execute_contract(owner.clone(), name_service.clone(), &revoke_msg, &[])
+        .is_err());
+    let execute_msg = ExecuteMsg::ExecuteDispute {
+        name: String::from("stolen"),
+    };
+    assert!(app
+        .execute_contract(owner.clone(), name_service.clone(), &execute_msg, &[])
+        .is_err());
+    // only the admin can cancel, the disputed name's owner can't
+    let cancel_msg = ExecuteMsg::CancelDispute {
+        name: String::from("stolen"),
+    };
+    assert!(app
+        .execute_contract(name_owner2.clone(), name_service.clone(), &cancel_msg, &[])
+        .is_err());
+    let result = app.execute_contract(owner.clone(), name_service.clone(), &cancel_msg, &[]);
+    assert!(result.is_ok());
+    assert_eq!(pending(&mut app), None);
+
+    let result = app.execute_contract(
+        owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::ReassignName {
+            name: String::from("stolen"),
+            new_owner: name_owner.clone(),
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    assert_eq!(name_info(&mut app).owner, name_owner2.to_string());
+    increment_block_time(&mut app, current_time + 1000, 100);
+    let result = app.execute_contract(owner.clone(), name_service.clone(), &execute_msg, &[]);
+    assert!(result.is_ok());
+    assert_eq!(name_info(&mut app).owner, name_owner.to_string());
+    assert_eq!(pending(&mut app), None);
+
+    // lowering the timelock waits out the current timelock
+    update_config.dispute_timelock = 0;
+    let result = app.execute_contract(
+        owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::UpdateConfig {
+            config: update_config,
+        },
+        &[],
+    );
+    assert!(result.is_ok());
+    let config: Config = query(&mut app, name_service.clone(), QueryMsg::Config {}).unwrap();
+    assert_eq!(config.dispute_timelock, 1000);
+    let reassign_msg = ExecuteMsg::ReassignName {
+        name: String::from("stolen"),
+        new_owner: name_owner2.clone(),
+    };
+    let result = app.execute_contract(owner.clone(), name_service.clone(), &reassign_msg, &[]);
+    assert!(result.is_ok());
+    assert_eq!(name_info(&mut app).owner, name_owner.to_string());
+    assert!(pending(&mut app).is_some());
+    let result = app.execute_contract(owner.clone(), name_service.clone(), &cancel_msg, &[]);
+    assert!(result.is_ok());
+
+    increment_block_time(&mut app, current_time + 2000, 100);
+    let config: Config = query(&mut app, name_service.clone(), QueryMsg::Config {}).unwrap();
+    assert_eq!(config.dispute_timelock, 0);
+    let result = app.execute_contract(owner.clone(), name_service.clone(), &reassign_msg, &[]);
+    assert!(result.is_ok());
+    assert_eq!(name_info(&mut app).owner, name_owner2.to_string());
+    assert_eq!(pending(&mut app), None);
+}
+
+#[test]
+fn test_events() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: update_config,
+    };
+    let attribute = |res: &AppResponse, ty: &str, key: &str| -> Option<String> {
+        res.events
+            .iter()
+            .find(|event| event.ty == format!("wasm-{}", ty))
+            .and_then(|event| event.attributes.iter().find(|attr| attr.key == key))
+            .map(|attr| attr.value.clone())
+    };
+
+    let result = app
+        .execute_contract(owner.clone(), name_service.clone(), &update_msg, &[])
+        .unwrap();
+    assert_eq!(
+        attribute(&result, "archid.config_updated", "cw721"),
+        Some(nft.to_string())
+    );
+    assert_eq!(
+        attribute(&result, "archid.config_updated", "base_expiration"),
+        Some(String::from("86400"))
+    );
+
+    let current_time = get_block_time(&mut app);
+    let result = app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::Register {
+                name: String::from("simpletest"),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        )
+        .unwrap();
+    for (key, value) in [
+        ("name", String::from("simpletest.arch")),
+        ("owner", name_owner.to_string()),
+        ("resolver", name_owner.to_string()),
+        ("cost", String::from("5000")),
+        ("created", current_time.to_string()),
+        ("expiration", (current_time + 86400).to_string()),
+    ] {
+        assert_eq!(attribute(&result, "archid.register", key), Some(value));
+    }
+
+    let result = app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("simpletest"),
+                subdomain: String::from("dapp"),
+                new_resolver: wallet.clone(),
+                new_owner: name_owner.clone(),
+                expiration: current_time + 43200,
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        attribute(&result, "archid.subdomain_registered", "name"),
+        Some(String::from("dapp.simpletest.arch"))
+    );
+    assert_eq!(
+        attribute(&result, "archid.subdomain_registered", "resolver"),
+        Some(wallet.to_string())
+    );
+    // minted by the domain owner, not bought on sale
+    assert_eq!(
+        attribute(&result, "archid.subdomain_registered", "price"),
+        None
+    );
+
+    let result = app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::UpdateResolver {
+                name: String::from("simpletest"),
+                new_resolver: wallet.clone(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        attribute(&result, "archid.resolver_changed", "resolver"),
+        Some(wallet.to_string())
+    );
+
+    let result = app
+        .execute_contract(
+            owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::Withdraw {
+                amount: Uint128::from(1000u128),
+                denom: None,
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        attribute(&result, "archid.withdraw", "recipient"),
+        Some(wallet.to_string())
+    );
+    assert_eq!(
+        attribute(&result, "archid.withdraw", "amount"),
+        Some(String::from("1000"))
+    );
+}
+
+#[test]
+fn test_event_decoding() {
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let sale = SubdomainSale {
+        price: Uint128::from(100u128),
+        denom: String::from(DENOM),
+        max_duration: 86400,
+    };
+    let events = vec![
+        RegistryEvent::Register(RegisterEvent {
+            name: String::from("simpletest.arch"),
+            owner: owner.clone(),
+            resolver: owner.clone(),
+            cost: Uint128::from(5000u128),
+            created: 1000,
+            expiration: 87400,
+        }),
+        RegistryEvent::Renew(RenewEvent {
+            name: String::from("simpletest.arch"),
+            payer: wallet.clone(),
+            cost: Uint128::from(5000u128),
+            expiration: 173800,
+        }),
+        RegistryEvent::ResolverChanged(ResolverChangedEvent {
+            name: String::from("simpletest.arch"),
+            resolver: wallet.clone(),
+        }),
+        RegistryEvent::OwnerChanged(OwnerChangedEvent {
+            name: String::from("simpletest.arch"),
+            owner: wallet.clone(),
+        }),
+        RegistryEvent::MetadataUpdated(MetadataUpdatedEvent {
+            name: String::from("simpletest.arch"),
+        }),
+        RegistryEvent::SubdomainRegistered(SubdomainRegisteredEvent {
+            name: String::from("dapp.simpletest.arch"),
+            domain: String::from("simpletest.arch"),
+            subdomain: String::from("dapp"),
+            owner: owner.clone(),
+            resolver: wallet.clone(),
+            created: 1000,
+            expiration: 2000,
+            price: None,
+        }),
+        RegistryEvent::SubdomainRegistered(SubdomainRegisteredEvent {
+            name: String::from("shop.simpletest.arch"),
+            domain: String::from("simpletest.arch"),
+            subdomain: String::from("shop"),
+            owner: wallet.clone(),
+            resolver: wallet.clone(),
+            created: 1000,
+            expiration: 2000,
+            price: Some(Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(100u128),
+            }),
+        }),
+        RegistryEvent::SubdomainExtended(SubdomainExtendedEvent {
+            name: String::from("dapp.simpletest.arch"),
+            domain: String::from("simpletest.arch"),
+            subdomain: String::from("dapp"),
+            expiration: 3000,
+        }),
+        RegistryEvent::SubdomainRemoved(SubdomainRemovedEvent {
+            name: String::from("dapp.simpletest.arch"),
+            domain: String::from("simpletest.arch"),
+            subdomain: String::from("dapp"),
+        }),
+        RegistryEvent::SubdomainSaleUpdated(SubdomainSaleUpdatedEvent {
+            domain: String::from("simpletest.arch"),
+            sale: Some(sale),
+        }),
+        RegistryEvent::SubdomainSaleUpdated(SubdomainSaleUpdatedEvent {
+            domain: String::from("simpletest.arch"),
+            sale: None,
+        }),
+        RegistryEvent::SubdomainFusesBurned(SubdomainFusesBurnedEvent {
+            name: String::from("dapp.simpletest.arch"),
+            fuses: PARENT_CANNOT_CONTROL | CANNOT_REMOVE,
+        }),
+        RegistryEvent::RenewalFundsUpdated(RenewalFundsUpdatedEvent {
+            name: String::from("simpletest.arch"),
+            balance: Uint128::from(250u128),
+        }),
+        RegistryEvent::NameRevoked(NameRevokedEvent {
+            name: String::from("simpletest.arch"),
+            reason: String::from("trademark, see case #12"),
+            previous_owner: owner.clone(),
+        }),
+        RegistryEvent::DisputeScheduled(DisputeScheduledEvent {
+            name: String::from("simpletest.arch"),
+            dispute: String::from("reassign"),
+            executable_at: 5000,
+        }),
+        RegistryEvent::DisputeCancelled(DisputeCancelledEvent {
+            name: String::from("simpletest.arch"),
+            dispute: String::from("revoke"),
+        }),
+        RegistryEvent::ConfigUpdated(ConfigUpdatedEvent {
+            config: Config {
+                admin: owner.clone(),
+                wallet: wallet.clone(),
+                cw721: Addr::unchecked("cw721"),
+                base_cost: Uint128::from(5000u64),
+                base_expiration: 86400,
+                subdomain_fee: Decimal::percent(5),
+                allow_third_party_renewals: true,
+                keeper_fee: Uint128::from(10u64),
+                renewal_window: 43200,
+                grace_period: 600,
+                dispute_timelock: 3600,
+                name_rules: NamingRules::default(),
+                subdomain_rules: NamingRules::default(),
+                cleanup_bounty: Uint128::zero(),
+            },
+        }),
+        RegistryEvent::Withdraw(WithdrawEvent {
+            recipient: wallet.clone(),
+            amount: Uint128::from(1000u128),
+            denom: String::from(DENOM),
+        }),
+    ];
+
+    for expected in events {
+        let event: Event = expected.clone().into();
+        assert_eq!(RegistryEvent::try_from(&event).unwrap(), expected);
+
+        // as indexed on chain
+        let mut indexed = event.clone();
+        indexed.ty = format!("wasm-{}", event.ty);
+        indexed = indexed.add_attribute("_contract_address", "contract0");
+        assert_eq!(RegistryEvent::try_from(&indexed).unwrap(), expected);
+    }
+
+    // parsing into the wrong event type fails
+    let event: Event = WithdrawEvent {
+        recipient: wallet.clone(),
+        amount: Uint128::from(1000u128),
+        denom: String::from(DENOM),
+    }
+    .into();
+    assert!(RenewEvent::try_from(&event).is_err());
+
+    // missing or malformed attributes fail
+    let missing = Event::new(RENEW_EVENT)
+        .add_attribute("name", "simpletest.arch")
+        .add_attribute("payer", "wallet")
+        .add_attribute("cost", "5000");
+    assert!(RenewEvent::try_from(&missing).is_err());
+    let malformed = missing.add_attribute("expiration", "tomorrow");
+    assert!(RenewEvent::try_from(&malformed).is_err());
+
+    // unknown event types fail
+    assert!(RegistryEvent::try_from(&Event::new("archid.unknown")).is_err());
+
+    // events emitted by the contract decode
+    let mut app = mock_app();
+    let name_owner = Addr::unchecked("mintnames");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let update_msg = ExecuteMsg::UpdateConfig {
+        config: Config {
+            admin: owner.clone(),
+            wallet: wallet.clone(),
+            cw721: nft.clone(),
+            base_cost: Uint128::from(5000u64),
+            base_expiration: 86400,
+            subdomain_fee: Decimal::zero(),
+            allow_third_party_renewals: false,
+            keeper_fee: Uint128::zero(),
+            renewal_window: 43200,
+            grace_period: 0,
+            dispute_timelock: 0,
+            name_rules: NamingRules::default(),
+            subdomain_rules: NamingRules::default(),
+            cleanup_bounty: Uint128::zero(),
+        },
+    };
+    app.execute_contract(owner.clone(), name_service.clone(), &update_msg, &[])
+        .unwrap();
+
+    let current_time = get_block_time(&mut app);
+    let result = app
+        .execute_contract(
+            name_owner.clone(),
+            name_service.clone(),
+            &ExecuteMsg::Register {
+                name: String::from("simpletest"),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        )
+        .unwrap();
+    let decoded: Vec<RegistryEvent> = result
+        .events
+        .iter()
+        .filter_map(|event| RegistryEvent::try_from(event).ok())
+        .collect();
+    assert_eq!(
+        decoded,
+        vec![RegistryEvent::Register(RegisterEvent {
+            name: String::from("simpletest.arch"),
+            owner: name_owner.clone(),
+            resolver: name_owner.clone(),
+            cost: Uint128::from(5000u128),
+            created: current_time,
+            expiration: current_time + 86400,
+        })]
+    );
+}
+
+#[test]
+fn test_registry_helper() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let wallet = Addr::unchecked("wallet");
+    let name_owner = Addr::unchecked("mintnames");
+    mint_native(
+        &mut app,
+        name_owner.to_string(),
+        String::from(DENOM),
+        Uint128::from(10000u128),
+    );
+    let name_service = create_name_service(
+        &mut app,
+        owner.clone(),
+        wallet.clone(),
+        owner.clone(),
+        Uint128::from(5000u64),
+        10000000,
+    );
+    let nft = create_cw721(&mut app, &name_service);
+    let update_config = Config {
+        admin: owner.clone(),
+        wallet: wallet.clone(),
+        cw721: nft.clone(),
+        base_cost: Uint128::from(5000u64),
+        base_expiration: 86400,
+        subdomain_fee: Decimal::zero(),
+        allow_third_party_renewals: false,
+        keeper_fee: Uint128::zero(),
+        renewal_window: 43200,
+        grace_period: 0,
+        dispute_timelock: 0,
+        name_rules: NamingRules::default(),
+        subdomain_rules: NamingRules::default(),
+        cleanup_bounty: Uint128::zero(),
+    };
+    app.execute_contract(
+        owner.clone(),
+        name_service.clone(),
+        &ExecuteMsg::UpdateConfig {
+            config: update_config.clone(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let registry = ArchIdRegistry(name_service.clone());
+    assert_eq!(registry.config(&app.wrap()).unwrap(), update_config);
+
+    let register_msg = registry
+        .register_msg(
+            "simpletest",
+            vec![Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        )
+        .unwrap();
+    let current_time = get_block_time(&mut app);
+    app.execute(name_owner.clone(), register_msg).unwrap();
+
+    let resolved = registry.resolve(&app.wrap(), "simpletest.arch").unwrap();
+    assert_eq!(resolved.address, Some(name_owner.to_string()));
+    assert_eq!(resolved.expiration, current_time + 86400);
+
+    let reverse = registry.reverse(&app.wrap(), &name_owner).unwrap();
+    assert_eq!(reverse.names, Some(vec![String::from("simpletest.arch")]));
+
+    let expiration = registry.expiration(&app.wrap(), "simpletest.arch").unwrap();
+    assert_eq!(expiration.created, current_time);
+
+    let info = registry.name_info(&app.wrap(), "simpletest.arch").unwrap();
+    assert_eq!(info.owner, name_owner.to_string());
+}
+
+#[test]
+fn test_registry_suite() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let mut suite = RegistrySuite::builder()
+        .with_pricing(1000, 86400)
+        .with_grace_period(3600)
+        .with_funds(&alice, 10000)
+        .build();
+
+    let config = suite.config();
+    assert_eq!(config.cw721, suite.cw721);
+    assert_eq!(config.base_cost, Uint128::from(1000u128));
+    assert_eq!(config.grace_period, 3600);
+    assert_eq!(suite.balance(&alice), Uint128::from(10000u128));
+
+    let registered = suite.block_time();
+    suite.register(&alice, "alice").unwrap();
+    assert_eq!(suite.balance(&alice), Uint128::from(9000u128));
+    assert_eq!(suite.owner_of("alice.arch").unwrap(), alice);
+    let resolved = suite.resolve("alice.arch").unwrap();
+    assert_eq!(resolved.address, Some(alice.to_string()));
+    assert_eq!(resolved.expiration, registered + 86400);
+
+    // bob has no funds
+    assert!(suite.register(&bob, "bob").is_err());
+
+    // expired names stop resolving but can be renewed during the grace period
+    suite.advance_time(86400 + 60);
+    assert_eq!(suite.block_time(), registered + 86400 + 60);
+    assert_eq!(suite.resolve("alice.arch").unwrap().address, None);
+    suite.renew(&alice, "alice").unwrap();
+    assert_eq!(
+        suite.resolve("alice.arch").unwrap().address,
+        Some(alice.to_string())
+    );
+}
+
+#[test]
+fn test_internationalized_names() {
+    let alice = Addr::unchecked("alice");
+    let mut suite = RegistrySuite::builder().with_funds(&alice, 100000).build();
+
+    // registered under the punycode form
+    suite.register(&alice, "日本語").unwrap();
+    assert_eq!(
+        suite.resolve("xn--wgv71a119e.arch").unwrap().address,
+        Some(alice.to_string())
+    );
+    assert_eq!(
+        suite.resolve("日本語.arch").unwrap().address,
+        Some(alice.to_string())
+    );
+    assert_eq!(suite.owner_of("xn--wgv71a119e.arch").unwrap(), alice);
+
+    // both forms name the same token
+    assert!(suite.register(&alice, "xn--wgv71a119e").is_err());
+
+    let info: NameInfoResponse = suite
+        .query(&QueryMsg::NameInfo {
+            name: String::from("日本語.arch"),
+        })
+        .unwrap();
+    assert_eq!(info.name, String::from("xn--wgv71a119e.arch"));
+    assert_eq!(info.unicode_name, String::from("日本語.arch"));
+    assert_eq!(info.metadata.name, Some(String::from("日本語")));
+    assert_eq!(
+        info.metadata.domain,
+        Some(String::from("xn--wgv71a119e.arch"))
+    );
+
+    // user metadata updates keep the unicode name
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::UpdateUserDomainData {
+                name: String::from("日本語"),
+                metadata_update: MetaDataUpdateMsg {
+                    description: Some(String::from("my domain")),
+                    image: None,
+                    accounts: None,
+                    websites: None,
+                },
+            },
+            &[],
+        )
+        .unwrap();
+    let info: NameInfoResponse = suite
+        .query(&QueryMsg::NameInfo {
+            name: String::from("日本語.arch"),
+        })
+        .unwrap();
+    assert_eq!(info.metadata.name, Some(String::from("日本語")));
+
+    let expiration = suite.resolve("日本語.arch").unwrap().expiration;
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("日本語"),
+                subdomain: String::from("東京"),
+                new_resolver: alice.clone(),
+                new_owner: alice.clone(),
+                expiration,
+            },
+            &[],
+        )
+        .unwrap();
+    let subdomains: SubdomainsResponse = suite
+        .query(&QueryMsg::Subdomains {
+            domain: String::from("日本語.arch"),
+            start_after: None,
+            limit: None,
+            include_expired: None,
+        })
+        .unwrap();
+    assert_eq!(subdomains.subdomains.len(), 1);
+    assert_eq!(
+        subdomains.subdomains[0].name,
+        String::from("xn--1lqs71d.xn--wgv71a119e.arch")
+    );
+    assert_eq!(
+        subdomains.subdomains[0].unicode_name,
+        String::from("東京.日本語.arch")
+    );
+    assert_eq!(
+        suite.owner_of("xn--1lqs71d.xn--wgv71a119e.arch").unwrap(),
+        alice
+    );
+
+    // latin mixed with cyrillic `а`
+    assert!(suite.register(&alice, "pаypal").is_err());
+    // cyrillic only, but looks like `ape`
+    assert!(suite.register(&alice, "аре").is_err());
+    // uppercase punycode names the same token
+    assert!(suite.register(&alice, "xn--WGV71A119E").is_err());
+    // ideographic full stop would split the label
+    assert!(suite.register(&alice, "日本。語").is_err());
+}
+
+#[test]
+fn test_name_parsing() {
+    let alice = Addr::unchecked("alice");
+    let mut suite = RegistrySuite::builder().with_funds(&alice, 100000).build();
+
+    // the suffix is not appended twice and input is lowercased
+    suite.register(&alice, "SimpleTest.arch").unwrap();
+    assert_eq!(suite.owner_of("simpletest.arch").unwrap(), alice);
+    for name in [
+        "simpletest",
+        "SIMPLETEST",
+        "simpletest.arch",
+        "SimpleTest.ARCH",
+    ] {
+        assert_eq!(
+            suite.resolve(name).unwrap().address,
+            Some(alice.to_string())
+        );
+    }
+    assert!(suite.register(&alice, "simpletest").is_err());
+
+    let expiration = suite.resolve("simpletest").unwrap().expiration;
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("simpletest.arch"),
+                subdomain: String::from("DApp"),
+                new_resolver: alice.clone(),
+                new_owner: alice.clone(),
+                expiration,
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.owner_of("dapp.simpletest.arch").unwrap(), alice);
+    assert_eq!(
+        suite.resolve("DAPP.simpletest").unwrap().address,
+        Some(alice.to_string())
+    );
+
+    // malformed names are rejected instead of panicking
+    for name in [
+        "",
+        ".arch",
+        "simpletest..arch",
+        "a.b.c.arch",
+        "sub.dapp.simpletest",
+    ] {
+        assert!(suite.resolve(name).is_err());
+    }
+    assert!(suite.register(&alice, "ab").is_err());
+    assert!(suite.register(&alice, "dapp.simpletest").is_err());
+}
+
+proptest! {
+    #[test]
+    fn parse_top_level_names(label in "[a-wyz0-9_][a-z0-9_-]{2,20}") {
+        let parser = NameParser::default();
+        let parsed = parser.parse(&label).unwrap();
+        prop_assert_eq!(parsed.depth(), 1);
+        prop_assert_eq!(parsed.label(), label.as_str());
+        prop_assert_eq!(parsed.name(), format!("{}.arch", label));
+        prop_assert!(parsed.parent().is_none());
+
+        // with or without the tld, in any case
+        for input in [
+            format!("{}.arch", label),
+            label.to_uppercase(),
+            format!("{}.ARCH", label.to_uppercase()),
+        ] {
+            prop_assert_eq!(parser.parse(&input).unwrap(), parsed.clone());
+        }
+        // parsing is idempotent
+        prop_assert_eq!(parser.parse(&parsed.name()).unwrap(), parsed.clone());
+    }
+
+    #[test]
+    fn parse_subdomains(
+        label in "[a-wyz0-9_][a-z0-9_-]{2,20}",
+        // never `arch`, which would be read as the tld
+        domain in "[b-wyz0-9_][a-z0-9_-]{2,20}",
+        extra in "[a-wyz0-9_][a-z0-9_-]{2,20}",
+    ) {
+        let parser = NameParser::default();
+        let parsed = parser.parse(&format!("{}.{}", label, domain)).unwrap();
+        prop_assert_eq!(parsed.depth(), 2);
+        prop_assert!(parsed.is_subdomain());
+        prop_assert_eq!(parsed.label(), label.as_str());
+        prop_assert_eq!(parsed.name(), format!("{}.{}.arch", label, domain));
+        prop_assert_eq!(parsed.parent().unwrap().name(), format!("{}.arch", domain));
+
+        // only top-level names and their subdomains exist
+        let too_deep = format!("{}.{}.{}.arch", extra, label, domain);
+        prop_assert!(parser.parse(&too_deep).is_err());
+    }
+
+    #[test]
+    fn parse_short_labels(label in "[a-z0-9]{1,2}") {
+        // short names parse, but can't be registered under the default rules
+        let parsed = NameParser::default().parse(&label).unwrap();
+        let rules = NamingRules::default();
+        prop_assert!(validate_name(&parsed.name(), &rules).is_err());
+        prop_assert!(validate_subdomain(parsed.label(), &rules).is_err());
+        prop_assert!(validate_top_level(&parsed.name()).is_ok());
+    }
+
+    #[test]
+    fn parse_arbitrary_input(input in any::<String>()) {
+        // never panics, and anything accepted is normalized
+        let parser = NameParser::default();
+        if let Ok(parsed) = parser.parse(&input) {
+            prop_assert!(parsed.depth() >= 1 && parsed.depth() <= MAX_DEPTH);
+            for label in parsed.labels.iter() {
+                prop_assert!(!label.is_empty() && label.len() as u64 <= MAX_LABEL_LENGTH);
+                prop_assert!(!label.chars().any(invalid_char));
+            }
+            prop_assert_eq!(parser.parse(&parsed.name()).unwrap(), parsed.clone());
+        }
+        let _ = validate_name(&input, &NamingRules::default());
+        let _ = validate_top_level(&input);
+        let _ = get_name_body(input.clone());
+    }
+}
+
+#[test]
+fn test_naming_rules() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let mut suite = RegistrySuite::builder().with_funds(&alice, 1000000).build();
+    let admin = suite.admin.clone();
+
+    // registered under the default rules
+    suite.register(&alice, "simpletest").unwrap();
+    let expiration = suite.resolve("simpletest.arch").unwrap().expiration;
+    let register_subdomain = |subdomain: &str, expiration: u64| ExecuteMsg::RegisterSubdomain {
+        domain: String::from("simpletest"),
+        subdomain: String::from(subdomain),
+        new_resolver: alice.clone(),
+        new_owner: alice.clone(),
+        expiration,
+    };
+    suite
+        .execute(&alice, &register_subdomain("dapp", expiration), &[])
+        .unwrap();
+
+    let name_rules = NamingRules {
+        min_length: 11,
+        max_length: 20,
+        allow_hyphens: true,
+        allow_underscores: false,
+        allow_unicode: false,
+        max_intervals: 5,
+    };
+    let subdomain_rules = NamingRules {
+        min_length: 5,
+        max_length: 20,
+        allow_hyphens: false,
+        allow_underscores: true,
+        allow_unicode: true,
+        max_intervals: 1,
+    };
+    let update_rules = |name_rules: &NamingRules, subdomain_rules: &NamingRules, c: &Config| {
+        ExecuteMsg::UpdateConfig {
+            config: Config {
+                name_rules: name_rules.clone(),
+                subdomain_rules: subdomain_rules.clone(),
+                ..c.clone()
+            },
+        }
+    };
+
+    // rules are validated and only the admin can change them
+    let config = suite.config();
+    let invalid_rules = vec![
+        NamingRules {
+            min_length: 0,
+            ..name_rules.clone()
+        },
+        NamingRules {
+            min_length: 21,
+            ..name_rules.clone()
+        },
+        NamingRules {
+            max_length: MAX_LABEL_LENGTH + 1,
+            ..name_rules.clone()
+        },
+        NamingRules {
+            max_intervals: 0,
+            ..name_rules.clone()
+        },
+    ];
+    for rules in invalid_rules {
+        assert!(suite
+            .execute(
+                &admin,
+                &update_rules(&rules, &subdomain_rules, &config),
+                &[]
+            )
+            .is_err());
+        assert!(suite
+            .execute(&admin, &update_rules(&name_rules, &rules, &config), &[])
+            .is_err());
+    }
+    assert!(suite
+        .execute(
+            &bob,
+            &update_rules(&name_rules, &subdomain_rules, &config),
+            &[]
+        )
+        .is_err());
+    suite
+        .execute(
+            &admin,
+            &update_rules(&name_rules, &subdomain_rules, &config),
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.config().name_rules, name_rules);
+    assert_eq!(suite.config().subdomain_rules, subdomain_rules);
+
+    // existing names keep working
+    suite.renew(&alice, "simpletest").unwrap();
+    let expiration = suite.resolve("simpletest.arch").unwrap().expiration;
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::ExtendSubdomainExpiry {
+                domain: String::from("simpletest"),
+                subdomain: String::from("dapp"),
+                expiration,
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        suite.resolve("dapp.simpletest.arch").unwrap().expiration,
+        expiration
+    );
+
+    // new names follow the new rules
+    assert!(suite.register(&alice, "abcdefghij").is_err());
+    assert!(suite.register(&alice, "abc_defghijk").is_err());
+    assert!(suite.register(&alice, "日本語日本語").is_err());
+    suite.register(&alice, "abc-defghijk").unwrap();
+    assert!(suite
+        .execute(&alice, &register_subdomain("shop", expiration), &[])
+        .is_err());
+    assert!(suite
+        .execute(&alice, &register_subdomain("web-shop", expiration), &[])
+        .is_err());
+    suite
+        .execute(&alice, &register_subdomain("web_shop", expiration), &[])
+        .unwrap();
+
+    // registrations can cover up to max_intervals
+    let current_time = suite.block_time();
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::Register {
+                name: String::from("longtermname"),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128 * 6),
+            }],
+        )
+        .unwrap();
+    assert_eq!(
+        suite.resolve("longtermname.arch").unwrap().expiration,
+        current_time + 86400 * 5
+    );
+
+    // lowering max_intervals never shortens a registration, renewals
+    // that would add no time fail and keep the payment
+    let lowered = NamingRules {
+        max_intervals: 2,
+        ..name_rules.clone()
+    };
+    let config = suite.config();
+    suite
+        .execute(
+            &admin,
+            &update_rules(&lowered, &subdomain_rules, &config),
+            &[],
+        )
+        .unwrap();
+    let balance = suite.balance(&alice);
+    assert_error(
+        suite.renew(&alice, "longtermname"),
+        "Name is registered for the maximum number of intervals",
+    );
+    assert!(suite
+        .execute(
+            &alice,
+            &ExecuteMsg::BatchRenew {
+                names: vec![String::from("longtermname")],
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        )
+        .is_err());
+    assert_eq!(suite.balance(&alice), balance);
+    assert_eq!(
+        suite.resolve("longtermname.arch").unwrap().expiration,
+        current_time + 86400 * 5
+    );
+}
+
+#[test]
+fn test_multiple_tlds() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let mut suite = RegistrySuite::builder().with_funds(&alice, 1000000).build();
+    let admin = suite.admin.clone();
+    let dao_cw721 = suite.add_tld("dao", 10000, 172800).unwrap();
+    let dao = suite.tld_config("simpletest.dao");
+    assert_eq!(dao.cw721, dao_cw721);
+
+    // only the admin can add valid tlds
+    let set_tld = |tld: &str, config: &TldConfig| ExecuteMsg::SetTld {
+        tld: tld.to_string(),
+        config: config.clone(),
+    };
+    assert!(suite.execute(&bob, &set_tld("app", &dao), &[]).is_err());
+    for tld in ["arch", "ARCH", "", "d.ao", "dao!"] {
+        assert!(suite.execute(&admin, &set_tld(tld, &dao), &[]).is_err());
+    }
+    let free = TldConfig {
+        base_cost: Uint128::zero(),
+        ..dao.clone()
+    };
+    assert!(suite.execute(&admin, &set_tld("app", &free), &[]).is_err());
+    let invalid_rules = TldConfig {
+        name_rules: NamingRules {
+            min_length: 0,
+            ..NamingRules::default()
+        },
+        ..dao.clone()
+    };
+    assert!(suite
+        .execute(&admin, &set_tld("app", &invalid_rules), &[])
+        .is_err());
+    // existing tlds keep their collection
+    let moved = TldConfig {
+        cw721: suite.cw721.clone(),
+        ..dao.clone()
+    };
+    assert!(suite.execute(&admin, &set_tld("dao", &moved), &[]).is_err());
+    // tlds can't take over a registered `.arch` name
+    suite.register(&alice, "taken").unwrap();
+    assert_error(
+        suite.execute(&admin, &set_tld("taken", &dao), &[]),
+        "Invalid tld (tld taken)",
+    );
+    // and `.arch` names can't take over a tld
+    assert_error(
+        suite.register(&alice, "dao.arch"),
+        &ContractError::NameShadowsTld {
+            name: String::from("dao.arch"),
+        }
+        .to_string(),
+    );
+    let res: TldsResponse = suite.query(&QueryMsg::Tlds {}).unwrap();
+    let tlds: Vec<&str> = res.tlds.iter().map(|info| info.tld.as_str()).collect();
+    assert_eq!(tlds, vec!["arch", "dao"]);
+    assert_eq!(res.tlds[0].config.cw721, suite.cw721);
+
+    // the same name is registered separately under each tld,
+    // priced and minted by its own tld
+    let current_time = suite.block_time();
+    suite.register(&alice, "simpletest").unwrap();
+    suite.register(&alice, "SimpleTest.DAO").unwrap();
+    assert_eq!(
+        suite.resolve("simpletest.arch").unwrap().expiration,
+        current_time + 86400
+    );
+    let expiration = suite.resolve("simpletest.dao").unwrap().expiration;
+    assert_eq!(expiration, current_time + 172800);
+    assert_eq!(suite.owner_of("simpletest.dao").unwrap(), alice);
+    let arch_token: Result<OwnerOfResponse, StdError> = suite.app.wrap().query_wasm_smart(
+        suite.cw721.clone(),
+        &Cw721QueryMsg::<Extension>::OwnerOf {
+            token_id: String::from("simpletest.dao"),
+            include_expired: None,
+        },
+    );
+    assert!(arch_token.is_err());
+    let underpaid = ExecuteMsg::Register {
+        name: String::from("underpaid.dao"),
+    };
+    assert!(suite
+        .execute(
+            &alice,
+            &underpaid,
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        )
+        .is_err());
+    // names under unknown tlds parse as `.arch` subdomains
+    assert!(suite.register(&alice, "simpletest.xyz").is_err());
+
+    // batches are priced per tld
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::BatchRegister {
+                names: vec![String::from("batchname"), String::from("batchname.dao")],
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(15000u128),
+            }],
+        )
+        .unwrap();
+    assert_eq!(
+        suite.resolve("batchname.arch").unwrap().expiration,
+        current_time + 86400
+    );
+    assert_eq!(
+        suite.resolve("batchname.dao").unwrap().expiration,
+        current_time + 172800
+    );
+
+    // subdomains are minted by their domain's tld
+    let register_subdomain = |subdomain: &str| ExecuteMsg::RegisterSubdomain {
+        domain: String::from("simpletest.dao"),
+        subdomain: String::from(subdomain),
+        new_resolver: alice.clone(),
+        new_owner: alice.clone(),
+        expiration,
+    };
+    suite
+        .execute(&alice, &register_subdomain("dapp"), &[])
+        .unwrap();
+    assert_eq!(
+        suite.resolve("dapp.simpletest.dao").unwrap().address,
+        Some(alice.to_string())
+    );
+    assert_eq!(suite.owner_of("dapp.simpletest.dao").unwrap(), alice);
+    let info: NameInfoResponse = suite
+        .query(&QueryMsg::NameInfo {
+            name: String::from("simpletest.dao"),
+        })
+        .unwrap();
+    assert_eq!(info.subdomain_count, 1);
+
+    // disabled tlds take no new names, existing ones keep working
+    let disabled = TldConfig {
+        enabled: false,
+        ..dao.clone()
+    };
+    let res = suite
+        .execute(&admin, &set_tld("dao", &disabled), &[])
+        .unwrap();
+    let event = res
+        .events
+        .iter()
+        .find(|e| e.ty == "wasm-archid.tld_updated")
+        .unwrap();
+    assert_eq!(
+        TldUpdatedEvent::try_from(event).unwrap(),
+        TldUpdatedEvent {
+            tld: String::from("dao"),
+            config: disabled,
+        }
+    );
+    assert!(suite.register(&alice, "newname.dao").is_err());
+    assert!(suite
+        .execute(&alice, &register_subdomain("shop"), &[])
+        .is_err());
+    suite.renew(&alice, "simpletest.dao").unwrap();
+    assert_eq!(
+        suite.resolve("simpletest.dao").unwrap().expiration,
+        expiration + 172800
+    );
+    suite.register(&alice, "newname").unwrap();
+
+    // a tld is only recognized as the last label
+    let parser = NameParser::default().with_tld("dao");
+    let parsed = parser.parse("DApp.SimpleTest.DAO").unwrap();
+    assert_eq!(parsed.tld, "dao");
+    assert_eq!(parsed.name(), "dapp.simpletest.dao");
+    let parsed = parser.parse("simpletest.dao.arch").unwrap();
+    assert_eq!(parsed.tld, "arch");
+    assert_eq!(parsed.labels, vec!["simpletest", "dao"]);
+    let parsed = NameParser::default().parse("simpletest.dao").unwrap();
+    assert_eq!(parsed.name(), "simpletest.dao.arch");
+    assert!(parsed.is_subdomain());
+}
+
+#[test]
+fn test_dns_records() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let mut suite = RegistrySuite::builder().with_funds(&alice, 1000000).build();
+    suite.register(&alice, "simpletest").unwrap();
+    suite.register(&alice, "my_name").unwrap();
+    let expiration = suite.resolve("simpletest.arch").unwrap().expiration;
+    let register_subdomain = |subdomain: &str| ExecuteMsg::RegisterSubdomain {
+        domain: String::from("simpletest"),
+        subdomain: String::from(subdomain),
+        new_resolver: alice.clone(),
+        new_owner: alice.clone(),
+        expiration,
+    };
+    suite
+        .execute(&alice, &register_subdomain("dapp"), &[])
+        .unwrap();
+    suite
+        .execute(&alice, &register_subdomain("my_app"), &[])
+        .unwrap();
+
+    let set_records = |name: &str, records: Vec<DnsRecord>| ExecuteMsg::SetDnsRecords {
+        name: name.to_string(),
+        records,
+    };
+    let set_mode = |name: &str, enabled: bool| ExecuteMsg::SetDnsMode {
+        name: name.to_string(),
+        enabled,
+    };
+    let record = |ttl: u32, data: DnsRecordData| DnsRecord { ttl, data };
+    let a = record(
+        300,
+        DnsRecordData::A {
+            address: String::from("192.0.2.1"),
+        },
+    );
+
+    // records need the domain in DNS mode, which needs LDH labels throughout
+    assert!(suite
+        .execute(&alice, &set_records("simpletest", vec![a.clone()]), &[])
+        .is_err());
+    assert!(suite
+        .execute(&alice, &set_mode("my_name", true), &[])
+        .is_err());
+    assert!(suite
+        .execute(&alice, &set_mode("simpletest", true), &[])
+        .is_err());
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::RemoveSubdomain {
+                domain: String::from("simpletest"),
+                subdomain: String::from("my_app"),
+            },
+            &[],
+        )
+        .unwrap();
+    assert!(suite
+        .execute(&bob, &set_mode("simpletest", true), &[])
+        .is_err());
+    let res = suite
+        .execute(&alice, &set_mode("simpletest", true), &[])
+        .unwrap();
+    let event = res
+        .events
+        .iter()
+        .find(|e| e.ty == "wasm-archid.dns_mode_updated")
+        .unwrap();
+    assert_eq!(
+        DnsModeUpdatedEvent::try_from(event).unwrap(),
+        DnsModeUpdatedEvent {
+            name: String::from("simpletest.arch"),
+            enabled: true,
+        }
+    );
+
+    // new subdomains must be LDH while in DNS mode
+    for label in ["new_app", "-app", "app-", "ab--cd"] {
+        assert!(suite
+            .execute(&alice, &register_subdomain(label), &[])
+            .is_err());
+    }
+    suite
+        .execute(&alice, &register_subdomain("new-app"), &[])
+        .unwrap();
+
+    // records are validated and normalized
+    let records = vec![
+        a.clone(),
+        record(
+            300,
+            DnsRecordData::Aaaa {
+                address: String::from("2001:DB8:0:0:0:0:0:1"),
+            },
+        ),
+        record(
+            3600,
+            DnsRecordData::Mx {
+                preference: 10,
+                exchange: String::from("Mail.Example.com."),
+            },
+        ),
+        record(
+            60,
+            DnsRecordData::Txt {
+                text: String::from("v=spf1 -all \"quoted\""),
+            },
+        ),
+    ];
+    let invalid = vec![
+        vec![record(0, a.data.clone())],
+        vec![record(
+            300,
+            DnsRecordData::A {
+                address: String::from("192.0.2.256"),
+            },
+        )],
+        vec![record(
+            300,
+            DnsRecordData::Cname {
+                target: String::from("bad_host.example.com"),
+            },
+        )],
+        vec![record(
+            300,
+            DnsRecordData::Txt {
+                text: String::from("line\nbreak"),
+            },
+        )],
+        vec![
+            a.clone(),
+            record(
+                300,
+                DnsRecordData::Cname {
+                    target: String::from("gateway.example.com"),
+                },
+            ),
+        ],
+        vec![a.clone(); MAX_DNS_RECORDS + 1],
+    ];
+    for records in invalid {
+        assert!(suite
+            .execute(&alice, &set_records("simpletest", records), &[])
+            .is_err());
+    }
+    assert!(suite
+        .execute(&bob, &set_records("simpletest", records.clone()), &[])
+        .is_err());
+    let res = suite
+        .execute(&alice, &set_records("simpletest", records), &[])
+        .unwrap();
+    let event = res
+        .events
+        .iter()
+        .find(|e| e.ty == "wasm-archid.dns_records_updated")
+        .unwrap();
+    let decoded = DnsRecordsUpdatedEvent::try_from(event).unwrap();
+    assert_eq!(decoded.name, "simpletest.arch");
+    assert_eq!(decoded.records.len(), 4);
+    assert_eq!(
+        decoded.records[1].data,
+        DnsRecordData::Aaaa {
+            address: String::from("2001:db8::1"),
+        }
+    );
+
+    let zone: DnsZoneResponse = suite
+        .query(&QueryMsg::DnsZone {
+            name: String::from("simpletest"),
+        })
+        .unwrap();
+    assert_eq!(zone.origin, "simpletest.arch.");
+    let lines: Vec<(String, u32, String, String, String)> = zone
+        .records
+        .into_iter()
+        .map(|r| (r.name, r.ttl, r.class, r.record_type, r.data))
+        .collect();
+    let line = |ttl: u32, record_type: &str, data: &str| {
+        (
+            String::from("simpletest.arch."),
+            ttl,
+            String::from("IN"),
+            record_type.to_string(),
+            data.to_string(),
+        )
+    };
+    assert_eq!(
+        lines,
+        vec![
+            line(300, "A", "192.0.2.1"),
+            line(300, "AAAA", "2001:db8::1"),
+            line(3600, "MX", "10 mail.example.com."),
+            line(60, "TXT", "\"v=spf1 -all \\\"quoted\\\"\""),
+        ]
+    );
+
+    // subdomains hold their own records
+    suite
+        .execute(
+            &alice,
+            &set_records(
+                "dapp.simpletest",
+                vec![record(
+                    300,
+                    DnsRecordData::Cname {
+                        target: String::from("Gateway.Example.com."),
+                    },
+                )],
+            ),
+            &[],
+        )
+        .unwrap();
+    let zone: DnsZoneResponse = suite
+        .query(&QueryMsg::DnsZone {
+            name: String::from("dapp.simpletest.arch"),
+        })
+        .unwrap();
+    assert_eq!(zone.records.len(), 1);
+    assert_eq!(zone.records[0].name, "dapp.simpletest.arch.");
+    assert_eq!(zone.records[0].record_type, "CNAME");
+    assert_eq!(zone.records[0].data, "gateway.example.com.");
+
+    // records are only served in DNS mode and while the name is active
+    suite
+        .execute(&alice, &set_mode("simpletest", false), &[])
+        .unwrap();
+    let zone: DnsZoneResponse = suite
+        .query(&QueryMsg::DnsZone {
+            name: String::from("simpletest"),
+        })
+        .unwrap();
+    assert!(zone.records.is_empty());
+    suite
+        .execute(&alice, &set_mode("simpletest", true), &[])
+        .unwrap();
+    let zone: DnsZoneResponse = suite
+        .query(&QueryMsg::DnsZone {
+            name: String::from("simpletest"),
+        })
+        .unwrap();
+    assert_eq!(zone.records.len(), 4);
+
+    // a new owner starts without DNS mode or records
+    let cw721 = suite.cw721.clone();
+    suite
+        .app
+        .execute_contract(
+            alice.clone(),
+            cw721,
+            &Cw721ExecuteMsg::TransferNft {
+                recipient: bob.to_string(),
+                token_id: String::from("simpletest.arch"),
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+        .execute(
+            &bob,
+            &ExecuteMsg::SyncOwnership {
+                name: String::from("simpletest"),
+            },
+            &[],
+        )
+        .unwrap();
+    let zone: DnsZoneResponse = suite
+        .query(&QueryMsg::DnsZone {
+            name: String::from("simpletest"),
+        })
+        .unwrap();
+    assert!(zone.records.is_empty());
+    assert_error(
+        suite.execute(&bob, &set_records("simpletest", vec![a.clone()]), &[]),
+        "Domain is not in DNS mode",
+    );
+    suite.set_time(expiration + 1);
+    let zone: DnsZoneResponse = suite
+        .query(&QueryMsg::DnsZone {
+            name: String::from("simpletest"),
+        })
+        .unwrap();
+    assert!(zone.records.is_empty());
+}
+
+// assert_error checks a failed call returns the contract error,
+// queries return it as the message of a generic error
+fn assert_error<T: std::fmt::Debug>(res: anyhow::Result<T>, expected: &str) {
+    let err = res.unwrap_err();
+    assert!(
+        err.root_cause().to_string().contains(expected),
+        "expected {}, got {:#}",
+        expected,
+        err
+    );
+}
+
+#[test]
+fn test_typed_errors() {
+    let admin = Addr::unchecked("admin");
+    let alice = Addr::unchecked("alice");
+    let mut suite = RegistrySuite::builder()
+        .with_admin(&admin)
+        .with_funds(&alice, 1000000)
+        .build();
+    suite.register(&alice, "simpletest").unwrap();
+    let expiration = suite.resolve("simpletest").unwrap().expiration;
+    let not_exists = |name: &str| {
+        ContractError::NameNotExists {
+            name: name.to_string(),
+        }
+        .to_string()
+    };
+    let subdomain_not_found = |name: &str| {
+        ContractError::SubdomainNotFound {
+            name: name.to_string(),
+        }
+        .to_string()
+    };
+
+    // unknown names
+    assert_error(
+        suite.query::<ResolveRecordResponse>(&QueryMsg::ResolveRecord {
+            name: String::from("unknown"),
+        }),
+        &not_exists("unknown.arch"),
+    );
+    assert_error(
+        suite.query::<RecordExpirationResponse>(&QueryMsg::RecordExpiration {
+            name: String::from("unknown"),
+        }),
+        &not_exists("unknown.arch"),
+    );
+    assert_error(
+        suite.query::<DnsZoneResponse>(&QueryMsg::DnsZone {
+            name: String::from("unknown"),
+        }),
+        &not_exists("unknown.arch"),
+    );
+    assert_error(
+        suite.execute(
+            &alice,
+            &ExecuteMsg::UpdateResolver {
+                name: String::from("unknown"),
+                new_resolver: alice.clone(),
+            },
+            &[],
+        ),
+        &not_exists("unknown.arch"),
+    );
+    assert_error(
+        suite.execute(
+            &alice,
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("unknown"),
+                subdomain: String::from("dapp"),
+                new_resolver: alice.clone(),
+                new_owner: alice.clone(),
+                expiration,
+            },
+            &[],
+        ),
+        &not_exists("unknown.arch"),
+    );
+
+    // unknown subdomains
+    assert_error(
+        suite.query::<ResolveRecordResponse>(&QueryMsg::ResolveRecord {
+            name: String::from("dapp.simpletest"),
+        }),
+        &subdomain_not_found("dapp.simpletest.arch"),
+    );
+    assert_error(
+        suite.query::<SubdomainFusesResponse>(&QueryMsg::SubdomainFuses {
+            name: String::from("dapp.simpletest"),
+        }),
+        &subdomain_not_found("dapp.simpletest.arch"),
+    );
+    assert_error(
+        suite.execute(
+            &alice,
+            &ExecuteMsg::ExtendSubdomainExpiry {
+                domain: String::from("simpletest"),
+                subdomain: String::from("dapp"),
+                expiration,
+            },
+            &[],
+        ),
+        &subdomain_not_found("dapp.simpletest.arch"),
+    );
+    assert_error(
+        suite.execute(
+            &alice,
+            &ExecuteMsg::RemoveSubdomain {
+                domain: String::from("simpletest"),
+                subdomain: String::from("dapp"),
+            },
+            &[],
+        ),
+        &subdomain_not_found("dapp.simpletest.arch"),
+    );
+    assert_error(
+        suite.execute(
+            &alice,
+            &ExecuteMsg::UpdateResolver {
+                name: String::from("dapp.simpletest"),
+                new_resolver: alice.clone(),
+            },
+            &[],
+        ),
+        &subdomain_not_found("dapp.simpletest.arch"),
+    );
+
+    // a cw721 without the name's token
+    let other_cw721 = suite.add_tld("dao", 1000, 86400).unwrap();
+    suite
+        .execute(
+            &admin,
+            &ExecuteMsg::UpdateConfig {
+                config: Config {
+                    cw721: other_cw721,
+                    ..suite.config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+    assert_error(
+        suite.execute(
+            &alice,
+            &ExecuteMsg::UpdateResolver {
+                name: String::from("simpletest"),
+                new_resolver: alice.clone(),
+            },
+            &[],
+        ),
+        "Name token query failed (name simpletest.arch",
+    );
+    assert_error(
+        suite.execute(
+            &alice,
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("simpletest"),
+                subdomain: String::from("dapp"),
+                new_resolver: alice.clone(),
+                new_owner: alice.clone(),
+                expiration,
+            },
+            &[],
+        ),
+        "Name token query failed (name simpletest.arch",
+    );
+
+    // registrations without a price
+    let mut suite = RegistrySuite::builder()
+        .with_pricing(0, 86400)
+        .with_funds(&alice, 1000000)
+        .build();
+    assert_error(
+        suite.execute(
+            &alice,
+            &ExecuteMsg::Register {
+                name: String::from("simpletest"),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(1000u128),
+            }],
+        ),
+        &ContractError::BaseCostNotSet {}.to_string(),
+    );
+}
+
+#[test]
+fn test_availability() {
+    let alice = Addr::unchecked("alice");
+    let mut suite = RegistrySuite::builder()
+        .with_grace_period(3600)
+        .with_funds(&alice, 1000000)
+        .build();
+    suite.register(&alice, "simpletest").unwrap();
+    let expiration = suite.resolve("simpletest").unwrap().expiration;
+    let availability = |suite: &RegistrySuite, name: &str| -> AvailabilityResponse {
+        suite
+            .query(&QueryMsg::Availability {
+                name: name.to_string(),
+            })
+            .unwrap()
+    };
+    let response = |name: &str, availability: Availability| AvailabilityResponse {
+        name: name.to_string(),
+        availability,
+    };
+    let invalid = |name: &str, err: ContractError| {
+        response(
+            name,
+            Availability::Invalid {
+                reason: err.to_string(),
+            },
+        )
+    };
+
+    assert_eq!(
+        availability(&suite, "SimpleTest.ARCH"),
+        response("simpletest.arch", Availability::Taken { until: expiration })
+    );
+    assert_eq!(
+        availability(&suite, "available"),
+        response("available.arch", Availability::Available)
+    );
+    assert_eq!(
+        availability(&suite, "ab"),
+        invalid(
+            "ab.arch",
+            ContractError::NameTooShort {
+                length: 2,
+                min_length: 3,
+            }
+        )
+    );
+    assert_eq!(
+        availability(&suite, "dapp.simpletest"),
+        invalid(
+            "dapp.simpletest.arch",
+            ContractError::NameTooDeep {
+                depth: 2,
+                max_depth: 1,
+            }
+        )
+    );
+    assert_eq!(
+        availability(&suite, "a.b.c"),
+        invalid(
+            "a.b.c",
+            ContractError::NameTooDeep {
+                depth: 3,
+                max_depth: 2,
+            }
+        )
+    );
+    assert!(matches!(
+        availability(&suite, "no spaces").availability,
+        Availability::Invalid { .. }
+    ));
+
+    // expired names are held for their owner during the grace period
+    suite.set_time(expiration + 1);
+    assert_eq!(
+        availability(&suite, "simpletest"),
+        response(
+            "simpletest.arch",
+            Availability::InGrace {
+                until: expiration + 3600
+            }
+        )
+    );
+    suite.set_time(expiration + 3601);
+    assert_eq!(
+        availability(&suite, "simpletest"),
+        response("simpletest.arch", Availability::Available)
+    );
+
+    // batches keep the order of the names queried
+    let batch: BatchAvailabilityResponse = suite
+        .query(&QueryMsg::BatchAvailability {
+            names: vec![
+                String::from("ab"),
+                String::from("simpletest"),
+                String::from("other"),
+            ],
+        })
+        .unwrap();
+    assert_eq!(
+        batch
+            .names
+            .into_iter()
+            .map(|item| item.name)
+            .collect::<Vec<String>>(),
+        vec!["ab.arch", "simpletest.arch", "other.arch"]
+    );
+    let too_many: Vec<String> = (0..31).map(|i| format!("name{}", i)).collect();
+    assert!(suite
+        .query::<BatchAvailabilityResponse>(&QueryMsg::BatchAvailability { names: too_many })
+        .is_err());
+
+    // reserved names can only be registered by the admin
+    let admin = suite.admin.clone();
+    suite.mint_native(&admin, 1000000);
+    let reserve = |reserved: bool| ExecuteMsg::SetReservedNames {
+        names: vec![String::from("Reserved"), String::from("simpletest")],
+        reserved,
+    };
+    assert!(suite.execute(&alice, &reserve(true), &[]).is_err());
+    suite.execute(&admin, &reserve(true), &[]).unwrap();
+    assert_eq!(
+        availability(&suite, "reserved"),
+        response("reserved.arch", Availability::Reserved)
+    );
+    assert_error(suite.register(&alice, "reserved"), "Name is reserved");
+    suite.register(&admin, "reserved").unwrap();
+    assert!(matches!(
+        availability(&suite, "reserved").availability,
+        Availability::Taken { .. }
+    ));
+    suite.execute(&admin, &reserve(false), &[]).unwrap();
+    assert_eq!(
+        availability(&suite, "simpletest"),
+        response("simpletest.arch", Availability::Available)
+    );
+}
+
+#[test]
+fn test_search_prefix() {
+    let alice = Addr::unchecked("alice");
+    let mut suite = RegistrySuite::builder().with_funds(&alice, 1000000).build();
+    for name in ["archway", "archid", "architect", "simpletest"] {
+        suite.register(&alice, name).unwrap();
+    }
+    let expiration = suite.resolve("archway").unwrap().expiration;
+    for (domain, subdomain) in [
+        ("archway", "app"),
+        ("archway", "api"),
+        ("archway", "web"),
+        ("simpletest", "archive"),
+    ] {
+        suite
+            .execute(
+                &alice,
+                &ExecuteMsg::RegisterSubdomain {
+                    domain: domain.to_string(),
+                    subdomain: subdomain.to_string(),
+                    new_resolver: alice.clone(),
+                    new_owner: alice.clone(),
+                    expiration,
+                },
+                &[],
+            )
+            .unwrap();
+    }
+    let search = |suite: &RegistrySuite,
+                  prefix: &str,
+                  scope: Option<SearchScope>,
+                  start_after: Option<&str>,
+                  limit: Option<u32>,
+                  include_expired: Option<bool>|
+     -> Vec<String> {
+        let res: SearchPrefixResponse = suite
+            .query(&QueryMsg::SearchPrefix {
+                prefix: prefix.to_string(),
+                scope,
+                start_after: start_after.map(String::from),
+                limit,
+                include_expired,
+            })
+            .unwrap();
+        res.names.into_iter().map(|item| item.name).collect()
+    };
+
+    // names and subdomains whose ascii form starts with the prefix
+    assert_eq!(
+        search(&suite, "Arch", None, None, None, None),
+        vec![
+            "archid.arch",
+            "architect.arch",
+            "archive.simpletest.arch",
+            "archway.arch",
+        ]
+    );
+    assert_eq!(
+        search(&suite, "arch", None, None, Some(2), None),
+        vec!["archid.arch", "architect.arch"]
+    );
+    assert_eq!(
+        search(&suite, "arch", None, Some("architect.arch"), Some(2), None),
+        vec!["archive.simpletest.arch", "archway.arch"]
+    );
+    assert!(search(&suite, "none", None, None, None, None).is_empty());
+    assert_eq!(
+        search(
+            &suite,
+            "arch",
+            Some(SearchScope::TopLevel),
+            None,
+            None,
+            None
+        ),
+        vec!["archid.arch", "architect.arch", "archway.arch"]
+    );
+
+    // subdomains of a parent match on their label
+    let subdomains = || {
+        Some(SearchScope::Subdomains {
+            parent: String::from("ArchWay"),
+        })
+    };
+    assert_eq!(
+        search(&suite, "a", subdomains(), None, None, None),
+        vec!["api.archway.arch", "app.archway.arch"]
+    );
+    assert_eq!(
+        search(
+            &suite,
+            "a",
+            subdomains(),
+            Some("api.archway.arch"),
+            None,
+            None
+        ),
+        vec!["app.archway.arch"]
+    );
+    assert_eq!(
+        search(&suite, "", subdomains(), None, None, None),
+        vec!["api.archway.arch", "app.archway.arch", "web.archway.arch"]
+    );
+    let res: SearchPrefixResponse = suite
+        .query(&QueryMsg::SearchPrefix {
+            prefix: String::from("arch"),
+            scope: Some(SearchScope::TopLevel),
+            start_after: None,
+            limit: Some(1),
+            include_expired: None,
+        })
+        .unwrap();
+    assert_eq!(
+        res.names,
+        vec![NameSummary {
+            name: String::from("archid.arch"),
+            unicode_name: String::from("archid.arch"),
+            expiration,
+        }]
+    );
+    // pages continue after the last name read, none is left after archway
+    assert_eq!(res.last_scanned, Some(String::from("archid.arch")));
+    let res: SearchPrefixResponse = suite
+        .query(&QueryMsg::SearchPrefix {
+            prefix: String::from("arch"),
+            scope: Some(SearchScope::TopLevel),
+            start_after: Some(String::from("architect.arch")),
+            limit: Some(1),
+            include_expired: None,
+        })
+        .unwrap();
+    assert_eq!(res.names.len(), 1);
+    assert_eq!(res.names[0].name, "archway.arch");
+    assert_eq!(res.last_scanned, None);
+
+    // expired names are left out unless asked for
+    suite.set_time(expiration + 1);
+    assert!(search(&suite, "arch", None, None, None, None).is_empty());
+    assert_eq!(
+        search(&suite, "arch", None, None, None, Some(true)).len(),
+        4
+    );
+}
+
+#[test]
+fn test_names_by_owner() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let carol = Addr::unchecked("carol");
+    let mut suite = RegistrySuite::builder()
+        .with_funds(&alice, 1000000)
+        .with_funds(&bob, 1000000)
+        .with_funds(&carol, 1000000)
+        .build();
+    suite.register(&alice, "alpha").unwrap();
+    suite.register(&alice, "beta").unwrap();
+    suite.register(&bob, "gamma").unwrap();
+    let expiration = suite.resolve("alpha").unwrap().expiration;
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("alpha"),
+                subdomain: String::from("app"),
+                new_resolver: bob.clone(),
+                new_owner: bob.clone(),
+                expiration,
+            },
+            &[],
+        )
+        .unwrap();
+    let names_by_owner = |suite: &RegistrySuite,
+                          owner: &Addr,
+                          start_after: Option<&str>,
+                          limit: Option<u32>,
+                          include_expired: Option<bool>|
+     -> Vec<String> {
+        let res: NamesByOwnerResponse = suite
+            .query(&QueryMsg::NamesByOwner {
+                owner: owner.clone(),
+                start_after: start_after.map(String::from),
+                limit,
+                include_expired,
+            })
+            .unwrap();
+        res.names.into_iter().map(|item| item.name).collect()
+    };
+
+    // minted names and subdomains
+    assert_eq!(
+        names_by_owner(&suite, &alice, None, None, None),
+        vec!["alpha.arch", "beta.arch"]
+    );
+    assert_eq!(
+        names_by_owner(&suite, &bob, None, None, None),
+        vec!["app.alpha.arch", "gamma.arch"]
+    );
+    assert_eq!(
+        names_by_owner(&suite, &alice, None, Some(1), None),
+        vec!["alpha.arch"]
+    );
+    assert_eq!(
+        names_by_owner(&suite, &alice, Some("alpha.arch"), None, None),
+        vec!["beta.arch"]
+    );
+    let res: NamesByOwnerResponse = suite
+        .query(&QueryMsg::NamesByOwner {
+            owner: bob.clone(),
+            start_after: None,
+            limit: Some(1),
+            include_expired: None,
+        })
+        .unwrap();
+    assert_eq!(
+        res.names,
+        vec![NameSummary {
+            name: String::from("app.alpha.arch"),
+            unicode_name: String::from("app.alpha.arch"),
+            expiration,
+        }]
+    );
+    assert_eq!(res.last_scanned, Some(String::from("app.alpha.arch")));
+
+    // transfers move names once synced
+    let cw721 = suite.cw721.clone();
+    suite
+        .app
+        .execute_contract(
+            alice.clone(),
+            cw721,
+            &Cw721ExecuteMsg::TransferNft {
+                recipient: bob.to_string(),
+                token_id: String::from("alpha.arch"),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        names_by_owner(&suite, &alice, None, None, None),
+        vec!["alpha.arch", "beta.arch"]
+    );
+    suite
+        .execute(
+            &bob,
+            &ExecuteMsg::SyncOwnership {
+                name: String::from("alpha"),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        names_by_owner(&suite, &alice, None, None, None),
+        vec!["beta.arch"]
+    );
+    assert_eq!(
+        names_by_owner(&suite, &bob, None, None, None),
+        vec!["alpha.arch", "app.alpha.arch", "gamma.arch"]
+    );
+
+    // removed subdomains leave the index
+    suite
+        .execute(
+            &bob,
+            &ExecuteMsg::RemoveSubdomain {
+                domain: String::from("alpha"),
+                subdomain: String::from("app"),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        names_by_owner(&suite, &bob, None, None, None),
+        vec!["alpha.arch", "gamma.arch"]
+    );
+
+    // expired names are left out unless asked for,
+    // re-registered names move to their new owner
+    suite.set_time(expiration + 1);
+    assert!(names_by_owner(&suite, &bob, None, None, None).is_empty());
+    assert_eq!(
+        names_by_owner(&suite, &bob, None, None, Some(true)),
+        vec!["alpha.arch", "gamma.arch"]
+    );
+    suite.register(&carol, "gamma").unwrap();
+    assert_eq!(
+        names_by_owner(&suite, &bob, None, None, Some(true)),
+        vec!["alpha.arch"]
+    );
+    assert_eq!(
+        names_by_owner(&suite, &carol, None, None, None),
+        vec!["gamma.arch"]
+    );
+}
+
+#[test]
+fn test_prune_expired() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let carol = Addr::unchecked("carol");
+    let mut suite = RegistrySuite::builder()
+        .with_grace_period(3600)
+        .with_cleanup_bounty(100)
+        .with_funds(&alice, 1000000)
+        .with_funds(&carol, 1000)
+        .build();
+    suite.register(&alice, "alpha").unwrap();
+    let alpha_expiration = suite.resolve("alpha").unwrap().expiration;
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("alpha"),
+                subdomain: String::from("app"),
+                new_resolver: bob.clone(),
+                new_owner: bob.clone(),
+                expiration: alpha_expiration,
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::DepositRenewalFunds {
+                name: String::from("alpha"),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(1000u128),
+            }],
+        )
+        .unwrap();
+    suite.advance_time(1000);
+    suite.register(&alice, "beta").unwrap();
+    let beta_expiration = suite.resolve("beta").unwrap().expiration;
+
+    // anyone can fund the pool
+    assert!(suite
+        .execute(&carol, &ExecuteMsg::FundCleanupPool {}, &[])
+        .is_err());
+    let res = suite
+        .execute(
+            &carol,
+            &ExecuteMsg::FundCleanupPool {},
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(150u128),
+            }],
+        )
+        .unwrap();
+    let event = res
+        .events
+        .iter()
+        .find(|e| e.ty == "wasm-archid.cleanup_pool_updated")
+        .unwrap();
+    assert_eq!(
+        CleanupPoolUpdatedEvent::try_from(event).unwrap(),
+        CleanupPoolUpdatedEvent {
+            balance: Uint128::from(150u128),
+        }
+    );
+    let pool: CleanupPoolResponse = suite.query(&QueryMsg::CleanupPool {}).unwrap();
+    assert_eq!(
+        pool,
+        CleanupPoolResponse {
+            balance: Uint128::from(150u128),
+            bounty: Uint128::from(100u128),
+        }
+    );
+
+    // the pool and the escrow can't be withdrawn as fees
+    let admin = suite.admin.clone();
+    let res = suite.execute(
+        &admin,
+        &ExecuteMsg::Withdraw {
+            amount: Uint128::from(10001u128),
+            denom: None,
+        },
+        &[],
+    );
+    assert_error(
+        res,
+        "Withdrawal exceeds the protocol fees held (available 10000)",
+    );
+    suite
+        .execute(
+            &admin,
+            &ExecuteMsg::Withdraw {
+                amount: Uint128::from(10000u128),
+                denom: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    let prune = |suite: &mut RegistrySuite| {
+        suite
+            .execute(&bob, &ExecuteMsg::PruneExpired { limit: None }, &[])
+            .unwrap()
+    };
+    let pruned = |res: &AppResponse| -> Vec<NamePrunedEvent> {
+        res.events
+            .iter()
+            .filter(|e| e.ty == "wasm-archid.name_pruned")
+            .map(|e| NamePrunedEvent::try_from(e).unwrap())
+            .collect()
+    };
+
+    // names are kept while active and during their grace period
+    assert!(pruned(&prune(&mut suite)).is_empty());
+    suite.set_time(alpha_expiration + 3599);
+    assert!(pruned(&prune(&mut suite)).is_empty());
+    assert_eq!(suite.balance(&bob), Uint128::zero());
+
+    // names past their grace period are burned with their subdomains
+    let alice_balance = suite.balance(&alice);
+    suite.set_time(alpha_expiration + 3600);
+    let res = prune(&mut suite);
+    assert_eq!(
+        pruned(&res),
+        vec![NamePrunedEvent {
+            name: String::from("alpha.arch"),
+            previous_owner: alice.clone(),
+            pruner: bob.clone(),
+        }]
+    );
+    assert!(res
+        .events
+        .iter()
+        .any(|e| e.ty == "wasm-archid.subdomain_removed"));
+    assert!(suite.resolve("alpha").is_err());
+    assert!(suite.resolve("app.alpha").is_err());
+    assert!(suite.owner_of("alpha.arch").is_err());
+    assert!(suite.owner_of("app.alpha.arch").is_err());
+    assert!(suite.resolve("beta").is_ok());
+    // the escrow goes back to the previous owner, the bounty to the pruner
+    assert_eq!(
+        suite.balance(&alice),
+        alice_balance + Uint128::from(1000u128)
+    );
+    assert_eq!(suite.balance(&bob), Uint128::from(100u128));
+    let availability: AvailabilityResponse = suite
+        .query(&QueryMsg::Availability {
+            name: String::from("alpha"),
+        })
+        .unwrap();
+    assert_eq!(availability.availability, Availability::Available);
+
+    // bounties are paid while the pool lasts
+    suite.set_time(beta_expiration + 3600);
+    let res = prune(&mut suite);
+    assert_eq!(pruned(&res).len(), 1);
+    assert_eq!(suite.balance(&bob), Uint128::from(150u128));
+    let pool: CleanupPoolResponse = suite.query(&QueryMsg::CleanupPool {}).unwrap();
+    assert_eq!(pool.balance, Uint128::zero());
+    let names: NamesByOwnerResponse = suite
+        .query(&QueryMsg::NamesByOwner {
+            owner: alice.clone(),
+            start_after: None,
+            limit: None,
+            include_expired: Some(true),
+        })
+        .unwrap();
+    assert!(names.names.is_empty());
+    assert!(pruned(&prune(&mut suite)).is_empty());
+    assert_eq!(suite.balance(&bob), Uint128::from(150u128));
+}
+
+#[test]
+fn test_backfill_indexes() {
+    let alice = Addr::unchecked("alice");
+    let mut suite = RegistrySuite::builder().with_funds(&alice, 1000000).build();
+    suite.register(&alice, "alpha").unwrap();
+    suite.register(&alice, "beta").unwrap();
+    suite.register(&alice, "gamma").unwrap();
+    let admin = suite.admin.clone();
+    let backfill = |start_after: Option<&str>| ExecuteMsg::BackfillIndexes {
+        start_after: start_after.map(String::from),
+        limit: Some(2),
+    };
+    let attribute = |res: &AppResponse, key: &str| -> Option<String> {
+        res.events
+            .iter()
+            .filter(|event| event.ty == "wasm")
+            .flat_map(|event| event.attributes.iter())
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+    };
+
+    // only the admin can backfill
+    assert!(suite.execute(&alice, &backfill(None), &[]).is_err());
+
+    // names are indexed a page at a time
+    let res = suite.execute(&admin, &backfill(None), &[]).unwrap();
+    assert_eq!(attribute(&res, "indexed"), Some(String::from("2")));
+    assert_eq!(
+        attribute(&res, "last_indexed"),
+        Some(String::from("beta.arch"))
+    );
+    let res = suite
+        .execute(&admin, &backfill(Some("beta.arch")), &[])
+        .unwrap();
+    assert_eq!(attribute(&res, "indexed"), Some(String::from("1")));
+    assert_eq!(
+        attribute(&res, "last_indexed"),
+        Some(String::from("gamma.arch"))
+    );
+    let res = suite
+        .execute(&admin, &backfill(Some("gamma.arch")), &[])
+        .unwrap();
+    assert_eq!(attribute(&res, "indexed"), Some(String::from("0")));
+    assert_eq!(attribute(&res, "last_indexed"), None);
+
+    // indexing names that are already indexed changes nothing
+    let res: NamesByOwnerResponse = suite
+        .query(&QueryMsg::NamesByOwner {
+            owner: alice.clone(),
+            start_after: None,
+            limit: None,
+            include_expired: None,
+        })
+        .unwrap();
+    assert_eq!(
+        res.names
+            .into_iter()
+            .map(|item| item.name)
+            .collect::<Vec<String>>(),
+        vec!["alpha.arch", "beta.arch", "gamma.arch"]
+    );
+}
+
+#[test]
+fn test_dispute_dropped_on_reregistration() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let mut suite = RegistrySuite::builder()
+        .with_dispute_timelock(1000)
+        .with_funds(&alice, 1000000)
+        .with_funds(&bob, 1000000)
+        .build();
+    let admin = suite.admin.clone();
+    suite.register(&alice, "alpha").unwrap();
+    suite
+        .execute(
+            &admin,
+            &ExecuteMsg::RevokeName {
+                name: String::from("alpha"),
+                reason: String::from("squatting"),
+            },
+            &[],
+        )
+        .unwrap();
+
+    // a dispute against the expired holder can't be executed against the new one
+    suite.advance_time(86401);
+    suite.register(&bob, "alpha").unwrap();
+    let res: PendingDisputeResponse = suite
+        .query(&QueryMsg::PendingDispute {
+            name: String::from("alpha.arch"),
+        })
+        .unwrap();
+    assert_eq!(res.dispute, None);
+    assert_error(
+        suite.execute(
+            &admin,
+            &ExecuteMsg::ExecuteDispute {
+                name: String::from("alpha"),
+            },
+            &[],
+        ),
+        "Name has no pending dispute",
+    );
+    assert_eq!(suite.owner_of("alpha.arch").unwrap(), bob);
+}
+
+#[test]
+fn test_subdomain_disputes_dropped() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let mut suite = RegistrySuite::builder()
+        .with_dispute_timelock(1000)
+        .with_funds(&alice, 1000000)
+        .build();
+    let admin = suite.admin.clone();
+    suite.register(&alice, "alpha").unwrap();
+    let start = suite.block_time();
+    let expiration = suite.resolve("alpha").unwrap().expiration;
+    let register_subdomain = |subdomain: &str, expiration: u64| ExecuteMsg::RegisterSubdomain {
+        domain: String::from("alpha"),
+        subdomain: subdomain.to_string(),
+        new_resolver: bob.clone(),
+        new_owner: bob.clone(),
+        expiration,
+    };
+    for (subdomain, expiration) in [
+        ("app", expiration),
+        ("web", expiration),
+        ("old", start + 2000),
+    ] {
+        suite
+            .execute(&alice, &register_subdomain(subdomain, expiration), &[])
+            .unwrap();
+    }
+    let revoke = |suite: &mut RegistrySuite, name: &str| {
+        suite
+            .execute(
+                &admin,
+                &ExecuteMsg::RevokeName {
+                    name: name.to_string(),
+                    reason: String::from("phishing"),
+                },
+                &[],
+            )
+            .unwrap();
+    };
+    let pending = |suite: &RegistrySuite, name: &str| {
+        let res: PendingDisputeResponse = suite
+            .query(&QueryMsg::PendingDispute {
+                name: name.to_string(),
+            })
+            .unwrap();
+        res.dispute
+    };
+
+    // removed subdomains drop their disputes, so a reminted
+    // subdomain can't be revoked by them
+    revoke(&mut suite, "app.alpha");
+    assert!(pending(&suite, "app.alpha.arch").is_some());
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::RemoveSubdomain {
+                domain: String::from("alpha"),
+                subdomain: String::from("app"),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(pending(&suite, "app.alpha.arch"), None);
+    suite
+        .execute(&alice, &register_subdomain("app", expiration), &[])
+        .unwrap();
+    suite.advance_time(1000);
+    assert_error(
+        suite.execute(
+            &admin,
+            &ExecuteMsg::ExecuteDispute {
+                name: String::from("app.alpha"),
+            },
+            &[],
+        ),
+        "Name has no pending dispute",
+    );
+
+    // expired subdomains drop their disputes when reminted
+    revoke(&mut suite, "old.alpha");
+    suite.set_time(start + 2001);
+    suite
+        .execute(&alice, &register_subdomain("old", expiration), &[])
+        .unwrap();
+    assert_eq!(pending(&suite, "old.alpha.arch"), None);
+
+    // subdomains removed with their domain drop their disputes
+    revoke(&mut suite, "web.alpha");
+    revoke(&mut suite, "alpha");
+    suite.advance_time(1000);
+    suite
+        .execute(
+            &admin,
+            &ExecuteMsg::ExecuteDispute {
+                name: String::from("alpha"),
+            },
+            &[],
+        )
+        .unwrap();
+    assert!(suite.resolve("web.alpha").is_err());
+    assert_eq!(pending(&suite, "web.alpha.arch"), None);
+    assert_eq!(pending(&suite, "alpha.arch"), None);
+}
+
+#[test]
+fn test_process_renewals_paging() {
+    let alice = Addr::unchecked("alice");
+    let keeper = Addr::unchecked("keeper");
+    let mut suite = RegistrySuite::builder()
+        .with_auto_renewals(100, 43200)
+        .with_funds(&alice, 100000)
+        .build();
+    for name in ["alpha", "bravo", "charlie", "delta"] {
+        suite.register(&alice, name).unwrap();
+    }
+    let expiration = suite.resolve("alpha").unwrap().expiration;
+    // alpha can't pay for a renewal, delta has no escrow at all
+    for (name, amount) in [("alpha", 1u128), ("bravo", 5100), ("charlie", 5100)] {
+        suite
+            .execute(
+                &alice,
+                &ExecuteMsg::DepositRenewalFunds {
+                    name: String::from(name),
+                },
+                &[Coin {
+                    denom: String::from(DENOM),
+                    amount: Uint128::from(amount),
+                }],
+            )
+            .unwrap();
+    }
+    suite.set_time(expiration - 40000);
+    let mut process = |start_after: Option<&str>, limit: Option<u32>| {
+        let res = suite
+            .execute(
+                &keeper,
+                &ExecuteMsg::ProcessRenewals {
+                    start_after: start_after.map(String::from),
+                    limit,
+                },
+                &[],
+            )
+            .unwrap();
+        let renewed = res
+            .events
+            .iter()
+            .filter(|event| event.ty == "wasm-archid.renew")
+            .map(|event| RenewEvent::try_from(event).unwrap().name)
+            .collect::<Vec<String>>();
+        let last_scanned = res
+            .events
+            .iter()
+            .filter(|event| event.ty == "wasm")
+            .flat_map(|event| event.attributes.iter())
+            .find(|attr| attr.key == "last_scanned")
+            .map(|attr| attr.value.clone());
+        (renewed, last_scanned)
+    };
+
+    // skipped names count against the limit
+    let (renewed, last_scanned) = process(None, Some(1));
+    assert!(renewed.is_empty());
+    assert_eq!(last_scanned, Some(String::from("alpha.arch")));
+    // a cursor without a record starts over, others are normalized
+    let (renewed, last_scanned) = process(Some("unknown"), Some(1));
+    assert!(renewed.is_empty());
+    assert_eq!(last_scanned, Some(String::from("alpha.arch")));
+    let (renewed, last_scanned) = process(Some("Alpha"), Some(1));
+    assert_eq!(renewed, vec!["bravo.arch"]);
+    assert_eq!(last_scanned, Some(String::from("bravo.arch")));
+
+    // bravo has left the window, so its cursor starts over, and
+    // names without escrow are never scanned
+    let (renewed, last_scanned) = process(Some("bravo.arch"), None);
+    assert_eq!(renewed, vec!["charlie.arch"]);
+    assert_eq!(last_scanned, Some(String::from("charlie.arch")));
+    let (renewed, last_scanned) = process(None, None);
+    assert!(renewed.is_empty());
+    assert_eq!(last_scanned, Some(String::from("alpha.arch")));
+    assert_eq!(suite.balance(&keeper), Uint128::from(200u128));
+    assert_eq!(suite.resolve("delta").unwrap().expiration, expiration);
+}
+
+#[test]
+fn test_prune_burned_outside_registry() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let carol = Addr::unchecked("carol");
+    let mut suite = RegistrySuite::builder()
+        .with_grace_period(3600)
+        .with_cleanup_bounty(100)
+        .with_funds(&alice, 1000000)
+        .with_funds(&carol, 1000)
+        .build();
+    suite.register(&alice, "alpha").unwrap();
+    suite.register(&alice, "beta").unwrap();
+    let expiration = suite.resolve("beta").unwrap().expiration;
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("beta"),
+                subdomain: String::from("app"),
+                new_resolver: bob.clone(),
+                new_owner: bob.clone(),
+                expiration,
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+        .execute(
+            &carol,
+            &ExecuteMsg::FundCleanupPool {},
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(1000u128),
+            }],
+        )
+        .unwrap();
+
+    // tokens burned on the cw721 contract directly
+    let cw721 = suite.cw721.clone();
+    for (owner, token_id) in [(&alice, "alpha.arch"), (&bob, "app.beta.arch")] {
+        suite
+            .app
+            .execute_contract(
+                owner.clone(),
+                cw721.clone(),
+                &Cw721ExecuteMsg::Burn {
+                    token_id: String::from(token_id),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    // don't hold up pruning the names after them
+    suite.set_time(expiration + 3600);
+    let res = suite
+        .execute(&bob, &ExecuteMsg::PruneExpired { limit: None }, &[])
+        .unwrap();
+    let pruned: Vec<NamePrunedEvent> = res
+        .events
+        .iter()
+        .filter(|e| e.ty == "wasm-archid.name_pruned")
+        .map(|e| NamePrunedEvent::try_from(e).unwrap())
+        .collect();
+    assert_eq!(
+        pruned,
+        vec![
+            NamePrunedEvent {
+                name: String::from("alpha.arch"),
+                previous_owner: alice.clone(),
+                pruner: bob.clone(),
+            },
+            NamePrunedEvent {
+                name: String::from("beta.arch"),
+                previous_owner: alice.clone(),
+                pruner: bob.clone(),
+            },
+        ]
+    );
+    assert!(res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .any(|attr| attr.key == "burn_failed"));
+    assert!(suite.resolve("alpha").is_err());
+    assert!(suite.resolve("beta").is_err());
+    assert!(suite.resolve("app.beta").is_err());
+    assert!(suite.owner_of("beta.arch").is_err());
+    assert_eq!(suite.balance(&bob), Uint128::from(200u128));
+}
+
+#[test]
+fn test_subdomain_update_resolver() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let carol = Addr::unchecked("carol");
+    let mut suite = RegistrySuite::builder().with_funds(&alice, 1000000).build();
+    suite.register(&alice, "alpha").unwrap();
+    let expiration = suite.resolve("alpha").unwrap().expiration;
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::RegisterSubdomain {
+                domain: String::from("alpha"),
+                subdomain: String::from("app"),
+                new_resolver: bob.clone(),
+                new_owner: bob.clone(),
+                expiration,
+            },
+            &[],
+        )
+        .unwrap();
+
+    // the subdomain owner points it at a new resolver
+    suite
+        .execute(
+            &bob,
+            &ExecuteMsg::UpdateResolver {
+                name: String::from("app.alpha"),
+                new_resolver: carol.clone(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        suite.resolve("app.alpha").unwrap().address,
+        Some(carol.to_string())
+    );
+
+    // and the parent's metadata follows
+    let parent: NftInfoResponse<Extension> = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            suite.cw721.clone(),
+            &Cw721QueryMsg::<Extension>::NftInfo {
+                token_id: String::from("alpha.arch"),
+            },
+        )
+        .unwrap();
+    let subdomains = parent.extension.unwrap().subdomains.unwrap();
+    assert_eq!(subdomains.len(), 1);
+    assert_eq!(subdomains[0].name, Some(String::from("app")));
+    assert_eq!(subdomains[0].resolver, Some(carol));
+    assert_eq!(subdomains[0].expiry, Some(expiration));
+}
+
+#[test]
+fn test_reassign_clears_previous_owner() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let mut suite = RegistrySuite::builder()
+        .with_auto_renewals(100, 43200)
+        .with_funds(&alice, 1000000)
+        .build();
+    let admin = suite.admin.clone();
+    suite.register(&alice, "alpha").unwrap();
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::SetDnsMode {
+                name: String::from("alpha"),
+                enabled: true,
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::DepositRenewalFunds {
+                name: String::from("alpha"),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        )
+        .unwrap();
+    let balance = suite.balance(&alice);
+
+    suite
+        .execute(
+            &admin,
+            &ExecuteMsg::ReassignName {
+                name: String::from("alpha"),
+                new_owner: bob.clone(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.owner_of("alpha.arch").unwrap(), bob);
+
+    // the previous owner's escrow is refunded instead of passing to the new owner
+    assert_eq!(suite.balance(&alice), balance + Uint128::from(5000u128));
+    let res: RenewalFundsResponse = suite
+        .query(&QueryMsg::RenewalFunds {
+            name: String::from("alpha.arch"),
+        })
+        .unwrap();
+    assert_eq!(res.balance, Uint128::zero());
+    assert_error(
+        suite.execute(
+            &bob,
+            &ExecuteMsg::WithdrawRenewalFunds {
+                name: String::from("alpha"),
+                amount: Uint128::from(5000u128),
+            },
+            &[],
+        ),
+        &ContractError::InvalidInput {}.to_string(),
+    );
+
+    // and DNS mode doesn't carry over to the new owner
+    assert_error(
+        suite.execute(
+            &bob,
+            &ExecuteMsg::SetDnsRecords {
+                name: String::from("alpha"),
+                records: vec![DnsRecord {
+                    ttl: 300,
+                    data: DnsRecordData::A {
+                        address: String::from("192.0.2.1"),
+                    },
+                }],
+            },
+            &[],
+        ),
+        &ContractError::DnsModeDisabled {
+            name: String::from("alpha.arch"),
+        }
+        .to_string(),
+    );
+}
+
+#[test]
+fn test_transfer_refunds_escrow() {
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let mut suite = RegistrySuite::builder()
+        .with_auto_renewals(100, 43200)
+        .with_funds(&alice, 1000000)
+        .build();
+    suite.register(&alice, "alpha").unwrap();
+    suite
+        .execute(
+            &alice,
+            &ExecuteMsg::DepositRenewalFunds {
+                name: String::from("alpha"),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: Uint128::from(5000u128),
+            }],
+        )
+        .unwrap();
+    let cw721 = suite.cw721.clone();
+    suite
+        .app
+        .execute_contract(
+            alice.clone(),
+            cw721,
+            &Cw721ExecuteMsg::TransferNft {
+                recipient: bob.to_string(),
+                token_id: String::from("alpha.arch"),
+            },
+            &[],
+        )
+        .unwrap();
+    let withdraw = ExecuteMsg::WithdrawRenewalFunds {
+        name: String::from("alpha"),
+        amount: Uint128::from(5000u128),
+    };
+
+    // the buyer can't withdraw the seller's escrow before the name is synced
+    assert_error(
+        suite.execute(&bob, &withdraw, &[]),
+        &ContractError::Unauthorized {}.to_string(),
+    );
+
+    // and syncing refunds it to the seller
+    let balance = suite.balance(&alice);
+    suite
+        .execute(
+            &bob,
+            &ExecuteMsg::SyncOwnership {
+                name: String::from("alpha"),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.balance(&alice), balance + Uint128::from(5000u128));
+    let res: RenewalFundsResponse = suite
+        .query(&QueryMsg::RenewalFunds {
+            name: String::from("alpha.arch"),
+        })
+        .unwrap();
+    assert_eq!(res.balance, Uint128::zero());
+    assert_error(
+        suite.execute(&bob, &withdraw, &[]),
+        &ContractError::InvalidInput {}.to_string(),
+    );
+}
+
+#[test]
+fn test_dispute_timelock_bounds() {
+    let alice = Addr::unchecked("alice");
+    let mut suite = RegistrySuite::builder().with_funds(&alice, 1000000).build();
+    let admin = suite.admin.clone();
+    suite.register(&alice, "alpha").unwrap();
+    let set_timelock = |suite: &mut RegistrySuite, dispute_timelock: u64| {
+        let config = Config {
+            dispute_timelock,
+            ..suite.config()
+        };
+        suite.execute(&admin, &ExecuteMsg::UpdateConfig { config }, &[])
+    };
+
+    // timelocks longer than the maximum are rejected
+    assert_error(
+        set_timelock(&mut suite, u64::MAX),
+        &ContractError::InvalidInput {}.to_string(),
+    );
+    set_timelock(&mut suite, MAX_DISPUTE_TIMELOCK).unwrap();
+
+    // the longest timelock still schedules disputes and can be lowered
+    let start = suite.block_time();
+    suite
+        .execute(
+            &admin,
+            &ExecuteMsg::RevokeName {
+                name: String::from("alpha"),
+                reason: String::from("squatting"),
+            },
+            &[],
+        )
+        .unwrap();
+    let res: PendingDisputeResponse = suite
+        .query(&QueryMsg::PendingDispute {
+            name: String::from("alpha.arch"),
+        })
+        .unwrap();
+    assert_eq!(
+        res.dispute.unwrap().executable_at,
+        start + MAX_DISPUTE_TIMELOCK
+    );
+    set_timelock(&mut suite, 0).unwrap();
+    assert_eq!(suite.config().dispute_timelock, MAX_DISPUTE_TIMELOCK);
+}
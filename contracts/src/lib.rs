@@ -4,10 +4,18 @@
 #![allow(deprecated)]
 
 pub mod contract;
+pub mod dns;
+pub mod events;
 pub mod handlers;
+#[cfg(any(feature = "library", test))]
+pub mod helpers;
+pub mod idn;
 pub mod msg;
+pub mod parser;
 pub mod read_utils;
 pub mod state;
+#[cfg(any(feature = "testing", test))]
+pub mod testing;
 pub mod write_utils;
 
 mod error;
@@ -1,5 +1,7 @@
-use crate::state::Config;
-use archid_token::{Account, Website};
+use crate::state::{
+    Config, DnsRecord, NameRecord, PendingDispute, SubDomainStatus, SubdomainSale, TldConfig,
+};
+use archid_token::{Account, Metadata, Website};
 use cosmwasm_std::{Addr, Uint128};
 
 use schemars::JsonSchema;
@@ -25,12 +27,22 @@ pub enum ExecuteMsg {
     Register {
         name: String,
     },
+    // sends protocol fees to the wallet, escrowed funds and
+    // the cleanup pool can't be withdrawn. `denom` defaults to the
+    // registry denom, other denoms hold fees from subdomain sales
     Withdraw {
         amount: Uint128,
+        denom: Option<String>,
     },
     RenewRegistration {
         name: String,
     },
+    BatchRegister {
+        names: Vec<String>,
+    },
+    BatchRenew {
+        names: Vec<String>,
+    },
     ExtendSubdomainExpiry {
         domain: String,
         subdomain: String,
@@ -58,6 +70,82 @@ pub enum ExecuteMsg {
         name: String,
         metadata_update: MetaDataUpdateMsg,
     },
+    SetSubdomainSale {
+        domain: String,
+        sale: Option<SubdomainSale>,
+    },
+    BurnSubdomainFuses {
+        domain: String,
+        subdomain: String,
+        fuses: u32,
+    },
+    DepositRenewalFunds {
+        name: String,
+    },
+    WithdrawRenewalFunds {
+        name: String,
+        amount: Uint128,
+    },
+    SyncOwnership {
+        name: String,
+    },
+    Reconcile {
+        names: Vec<String>,
+    },
+    // reserves top-level names for the admin, or releases them
+    SetReservedNames {
+        names: Vec<String>,
+        reserved: bool,
+    },
+    // indexes names saved before the expiration, subdomain and owner indexes,
+    // one page at a time from the last name indexed
+    BackfillIndexes {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    RevokeName {
+        name: String,
+        reason: String,
+    },
+    ReassignName {
+        name: String,
+        new_owner: Addr,
+    },
+    ExecuteDispute {
+        name: String,
+    },
+    CancelDispute {
+        name: String,
+    },
+    // renews escrow-funded names due within the renewal window, `limit`
+    // counts every name looked at, renewed or not, and the `last_scanned`
+    // attribute is the `start_after` for the next call
+    ProcessRenewals {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // adds the funds sent to the pool paying cleanup bounties
+    FundCleanupPool {},
+    // removes names past their grace period, paying the sender a bounty per name
+    PruneExpired {
+        limit: Option<u32>,
+    },
+    // adds or updates an additional tld, e.g. `dao` for `.dao` names. A tld's
+    // cw721 can't change, and no tld can shadow a registered `.arch` name
+    SetTld {
+        tld: String,
+        config: TldConfig,
+    },
+    // opts a top-level domain and its subdomains in or out of DNS compatible labels
+    SetDnsMode {
+        name: String,
+        enabled: bool,
+    },
+    // replaces the DNS records of a name in DNS mode
+    SetDnsRecords {
+        name: String,
+        records: Vec<DnsRecord>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -67,10 +155,74 @@ pub struct MigrateMsg {}
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    ResolveRecord { name: String },
-    RecordExpiration { name: String },
-    ResolveAddress { address: Addr },
+    ResolveRecord {
+        name: String,
+    },
+    RecordExpiration {
+        name: String,
+    },
+    ResolveAddress {
+        address: Addr,
+    },
     Config {},
+    SubdomainSale {
+        domain: String,
+    },
+    SubdomainFuses {
+        name: String,
+    },
+    RenewalFunds {
+        name: String,
+    },
+    ExpiringBetween {
+        from: u64,
+        to: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    NameInfo {
+        name: String,
+    },
+    VerifyConsistency {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    PendingDispute {
+        name: String,
+    },
+    Subdomains {
+        domain: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        include_expired: Option<bool>,
+    },
+    Tlds {},
+    CleanupPool {},
+    DnsZone {
+        name: String,
+    },
+    // whether a name can be registered, and why not
+    Availability {
+        name: String,
+    },
+    BatchAvailability {
+        names: Vec<String>,
+    },
+    // names owned by an address, as last synced with the cw721 contract
+    NamesByOwner {
+        owner: Addr,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        include_expired: Option<bool>,
+    },
+    // names whose ascii form starts with the prefix, in key order
+    SearchPrefix {
+        prefix: String,
+        scope: Option<SearchScope>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        include_expired: Option<bool>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -87,3 +239,183 @@ pub struct RecordExpirationResponse {
     pub created: u64,
     pub expiration: u64,
 }
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SubdomainSaleResponse {
+    pub sale: Option<SubdomainSale>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SubdomainFusesResponse {
+    // fuses still in effect, burned fuses lapse when the subdomain expires
+    pub fuses: u32,
+    pub expiration: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RenewalFundsResponse {
+    pub balance: Uint128,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ExpiringName {
+    pub name: String,
+    pub expiration: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ExpiringBetweenResponse {
+    pub names: Vec<ExpiringName>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SubdomainInfo {
+    // ascii form, the subdomain's token id
+    pub name: String,
+    pub unicode_name: String,
+    pub record: NameRecord,
+    pub owner: String,
+    pub status: SubDomainStatus,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SubdomainsResponse {
+    pub subdomains: Vec<SubdomainInfo>,
+    // label to pass as `start_after` for the next page, as in SearchPrefixResponse
+    pub last_scanned: Option<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NameStatus {
+    Active,
+    // expired, but only the owner can renew it until the grace period ends
+    InGrace,
+    Expired,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NameInfoResponse {
+    // ascii form, the name's token id
+    pub name: String,
+    pub unicode_name: String,
+    pub resolver: Addr,
+    pub created: u64,
+    pub expiration: u64,
+    pub status: NameStatus,
+    pub owner: String,
+    pub metadata: Metadata,
+    pub subdomain_count: u64,
+    pub parent: Option<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsistencyIssue {
+    MissingToken,
+    OwnerMismatch {
+        registry: Option<Addr>,
+        token: Addr,
+    },
+    ExpiryMismatch {
+        registry: u64,
+        token: Option<u64>,
+    },
+    // subdomain labels missing from, outdated in or unknown to the domain's metadata
+    SubdomainsMismatch {
+        missing: Vec<String>,
+        stale: Vec<String>,
+        unknown: Vec<String>,
+    },
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NameMismatch {
+    pub name: String,
+    pub issues: Vec<ConsistencyIssue>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VerifyConsistencyResponse {
+    pub mismatches: Vec<NameMismatch>,
+    pub last_checked: Option<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingDisputeResponse {
+    pub dispute: Option<PendingDispute>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TldInfo {
+    pub tld: String,
+    pub config: TldConfig,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TldsResponse {
+    pub tlds: Vec<TldInfo>,
+}
+// One line of a zone file, e.g. `simpletest.arch. 300 IN A 192.0.2.1`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ZoneRecord {
+    // fully qualified name
+    pub name: String,
+    pub ttl: u32,
+    pub class: String,
+    pub record_type: String,
+    pub data: String,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DnsZoneResponse {
+    pub origin: String,
+    // empty unless the name is active and its domain is in DNS mode
+    pub records: Vec<ZoneRecord>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+    Available,
+    // registered until the expiration
+    Taken { until: u64 },
+    // expired, only the owner can renew it until the grace period ends
+    InGrace { until: u64 },
+    // expired name sold at a premium over its base cost, not returned
+    // until premium auctions are added
+    PremiumAuction { price: Uint128 },
+    // held back by the admin, only the admin can register it
+    Reserved,
+    // the name can't be registered, e.g. it breaks the naming rules
+    Invalid { reason: String },
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AvailabilityResponse {
+    // ascii form of valid names, invalid names as given
+    pub name: String,
+    pub availability: Availability,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BatchAvailabilityResponse {
+    // in the order of the names queried
+    pub names: Vec<AvailabilityResponse>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchScope {
+    // top-level names and subdomains, the default
+    All,
+    TopLevel,
+    // subdomains of a top-level name, the prefix matches their label
+    Subdomains { parent: String },
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NameSummary {
+    // ascii form, the name's token id
+    pub name: String,
+    pub unicode_name: String,
+    pub expiration: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SearchPrefixResponse {
+    pub names: Vec<NameSummary>,
+    // `start_after` for the next page, none once every name has been read.
+    // Pages can hold fewer than `limit` names, as names left out are read too
+    pub last_scanned: Option<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NamesByOwnerResponse {
+    pub names: Vec<NameSummary>,
+    // `start_after` for the next page, as in SearchPrefixResponse
+    pub last_scanned: Option<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CleanupPoolResponse {
+    pub balance: Uint128,
+    // paid for each name pruned while the pool lasts
+    pub bounty: Uint128,
+}
@@ -0,0 +1,146 @@
+// Parsing of user supplied names. Every name entering execute or query
+// dispatch goes through NameParser, so handlers only ever see the
+// normalized ascii form, e.g. `DApp.SimpleTest`, `dapp.simpletest.arch`
+// and `dapp.simpletest.ARCH` all parse to `dapp.simpletest.arch`.
+// Names ending in an additional tld (e.g. `simpletest.dao`) are parsed
+// under that tld, any other name is under the default `.arch` tld.
+use cosmwasm_std::{Order, StdResult, Storage};
+
+use crate::error::ContractError;
+use crate::idn::{normalize_label, to_unicode};
+use crate::read_utils::invalid_char;
+use crate::state::{tlds_read, MAX_LABEL_LENGTH};
+
+// default tld
+pub const TLD: &str = "arch";
+// top-level names and their subdomains
+pub const MAX_DEPTH: usize = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedName {
+    // ascii labels without the tld, most specific first
+    pub labels: Vec<String>,
+    pub tld: String,
+}
+
+impl ParsedName {
+    // 1 for top-level names, 2 for subdomains
+    pub fn depth(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_subdomain(&self) -> bool {
+        self.depth() > 1
+    }
+
+    // ascii name including the tld, as used for storage keys and token ids
+    pub fn name(&self) -> String {
+        format!("{}.{}", self.body(), self.tld)
+    }
+
+    pub fn unicode_name(&self) -> String {
+        to_unicode(&self.name())
+    }
+
+    // ascii name without the tld
+    pub fn body(&self) -> String {
+        self.labels.join(".")
+    }
+
+    // the most specific label, e.g. `dapp` for `dapp.simpletest.arch`
+    pub fn label(&self) -> &str {
+        &self.labels[0]
+    }
+
+    pub fn parent(&self) -> Option<ParsedName> {
+        match self.is_subdomain() {
+            true => Some(ParsedName {
+                labels: self.labels[1..].to_vec(),
+                tld: self.tld.clone(),
+            }),
+            false => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameParser {
+    // the default tld first
+    tlds: Vec<String>,
+    max_depth: usize,
+}
+
+impl Default for NameParser {
+    fn default() -> Self {
+        NameParser {
+            tlds: vec![TLD.to_string()],
+            max_depth: MAX_DEPTH,
+        }
+    }
+}
+
+impl NameParser {
+    /// load returns a parser for the default tld and the tlds added by the admin
+    pub fn load(storage: &dyn Storage) -> StdResult<Self> {
+        let mut parser = NameParser::default();
+        for item in tlds_read(storage).range(None, None, Order::Ascending) {
+            let (tld, _config) = item?;
+            parser.tlds.push(String::from_utf8(tld)?);
+        }
+        Ok(parser)
+    }
+
+    pub fn with_tld(mut self, tld: &str) -> Self {
+        self.tlds.push(tld.to_string());
+        self
+    }
+
+    /// parse accepts a name with or without its tld, in any case,
+    /// in unicode or in punycode
+    pub fn parse(&self, input: &str) -> Result<ParsedName, ContractError> {
+        let input = input.to_ascii_lowercase();
+        let mut labels: Vec<&str> = input.split('.').collect();
+        let last = labels[labels.len() - 1];
+        let tld = match labels.len() > 1 && self.tlds.iter().any(|tld| tld == last) {
+            true => labels.pop().unwrap_or(TLD).to_string(),
+            false => self.tlds[0].clone(),
+        };
+        if labels.len() > self.max_depth {
+            return Err(ContractError::NameTooDeep {
+                depth: labels.len() as u64,
+                max_depth: self.max_depth as u64,
+            });
+        }
+        let labels = labels
+            .into_iter()
+            .map(|label| self.parse_label(label))
+            .collect::<Result<Vec<String>, ContractError>>()?;
+        Ok(ParsedName { labels, tld })
+    }
+
+    /// parse_label normalizes a single label and returns its ascii form,
+    /// the naming rules are only checked when names are registered
+    pub fn parse_label(&self, input: &str) -> Result<String, ContractError> {
+        let input = input.to_ascii_lowercase();
+        if input.is_empty() || input.contains('.') {
+            return Err(ContractError::InvalidInput {});
+        }
+        let label = normalize_label(&input)?.ascii;
+        let length = label.len() as u64;
+        if length > MAX_LABEL_LENGTH {
+            return Err(ContractError::NameTooLong {
+                length,
+                max_length: MAX_LABEL_LENGTH,
+            });
+        }
+        match label.chars().find(|c| invalid_char(*c)) {
+            Some(c) => Err(ContractError::InvalidCharacter { c }),
+            None => Ok(label),
+        }
+    }
+
+    /// normalize returns the ascii name including the tld
+    pub fn normalize(&self, input: &str) -> Result<String, ContractError> {
+        Ok(self.parse(input)?.name())
+    }
+}
@@ -1,22 +1,48 @@
 use cosmwasm_std::{
-    to_json_binary, Addr, Binary, BlockInfo, Deps, DepsMut, Env, Order, QueryRequest, Record,
-    StdError, StdResult, WasmQuery,
+    to_json_binary, Addr, Binary, BlockInfo, Deps, DepsMut, Env, Order, QuerierWrapper,
+    QueryRequest, Record, StdError, StdResult, Storage, WasmQuery,
 };
 
-use archid_token::{Extension, Metadata, QueryMsg as Cw721QueryMsg};
+use archid_token::{Extension, Metadata, QueryMsg as Cw721QueryMsg, Subdomain};
 use cw721_updatable::{NftInfoResponse, OwnerOfResponse};
 
 use crate::error::ContractError;
-use crate::msg::{RecordExpirationResponse, ResolveAddressResponse, ResolveRecordResponse};
-use crate::state::{resolver_read, NameRecord};
+use crate::idn::to_unicode;
+use crate::msg::{
+    Availability, AvailabilityResponse, BatchAvailabilityResponse, CleanupPoolResponse,
+    ConsistencyIssue, DnsZoneResponse, ExpiringBetweenResponse, ExpiringName, NameInfoResponse,
+    NameMismatch, NameStatus, NameSummary, NamesByOwnerResponse, PendingDisputeResponse,
+    RecordExpirationResponse, RenewalFundsResponse, ResolveAddressResponse, ResolveRecordResponse,
+    SearchPrefixResponse, SearchScope, SubdomainFusesResponse, SubdomainInfo,
+    SubdomainSaleResponse, SubdomainsResponse, TldInfo, TldsResponse, VerifyConsistencyResponse,
+    ZoneRecord,
+};
+use crate::state::{
+    cleanup_pool_read, config_read, dns_mode_read, dns_records_read, expiration_index_read,
+    expiration_key, name_owner_read, name_tld, owner_index_read, parent_name, pending_dispute_read,
+    renewal_funds_read, reserved_names_read, resolver_read, subdomain_fuses_read,
+    subdomain_index_read, subdomain_sale_read, timelock_decrease_read, tlds_read, Config,
+    NameRecord, NamingRules, SubDomainStatus, TldConfig, ALL_FUSES,
+};
+
+use crate::parser::{NameParser, TLD};
 
-const MIN_NAME_LENGTH: u64 = 3;
-const MAX_NAME_LENGTH: u64 = 64;
-const SUFFIX: &str = ".arch";
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+// entries a filtered listing reads at most, filtered out entries included
+const MAX_SCAN: usize = 300;
 pub fn query_name_owner(
     id: &str,
     cw721: &Addr,
     deps: &DepsMut,
+) -> Result<OwnerOfResponse, ContractError> {
+    query_owner_of(&deps.querier, id, cw721).map_err(|err| cw721_query_failed(id, err))
+}
+
+pub fn query_owner_of(
+    querier: &QuerierWrapper,
+    id: &str,
+    cw721: &Addr,
 ) -> Result<OwnerOfResponse, StdError> {
     let query_msg: archid_token::QueryMsg<Extension> = Cw721QueryMsg::OwnerOf {
         token_id: id.to_owned(),
@@ -24,15 +50,14 @@ pub fn query_name_owner(
     };
     let req = QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: cw721.to_string(),
-        msg: to_json_binary(&query_msg).unwrap(),
+        msg: to_json_binary(&query_msg)?,
     });
-    let res: OwnerOfResponse = deps.querier.query(&req)?;
+    let res: OwnerOfResponse = querier.query(&req)?;
     Ok(res)
 }
 
 pub fn query_resolver(deps: Deps, env: Env, name: String) -> StdResult<Binary> {
-    let key = name.as_bytes();
-    let curr = (resolver_read(deps.storage).may_load(key)?).unwrap();
+    let curr = load_record(deps.storage, &name).map_err(query_error)?;
 
     let address = match curr.is_expired(&env.block) {
         true => None,
@@ -47,8 +72,7 @@ pub fn query_resolver(deps: Deps, env: Env, name: String) -> StdResult<Binary> {
 }
 
 pub fn query_resolver_expiration(deps: Deps, _env: Env, name: String) -> StdResult<Binary> {
-    let key = name.as_bytes();
-    let curr = (resolver_read(deps.storage).may_load(key)?).unwrap();
+    let curr = load_record(deps.storage, &name).map_err(query_error)?;
     let resp = RecordExpirationResponse {
         created: curr.created,
         expiration: curr.expiration,
@@ -57,11 +81,9 @@ pub fn query_resolver_expiration(deps: Deps, _env: Env, name: String) -> StdResu
 }
 
 pub fn query_resolver_address(deps: Deps, env: Env, address: Addr) -> StdResult<Binary> {
-    let curr: StdResult<Vec<Record<NameRecord>>> = resolver_read(deps.storage)
+    let records: Vec<Record<NameRecord>> = resolver_read(deps.storage)
         .range(None, None, Order::Ascending)
-        .collect();
-
-    let records = curr.unwrap();
+        .collect::<StdResult<_>>()?;
 
     let names = records
         .into_iter()
@@ -75,7 +97,7 @@ pub fn query_resolver_address(deps: Deps, env: Env, address: Addr) -> StdResult<
 
     let mut output_names = vec![];
     for (key, _record) in unexpired_names.into_iter() {
-        output_names.push(String::from_utf8(key).unwrap());
+        output_names.push(String::from_utf8(key)?);
     }
 
     let resp = ResolveAddressResponse {
@@ -84,91 +106,753 @@ pub fn query_resolver_address(deps: Deps, env: Env, address: Addr) -> StdResult<
     to_json_binary(&resp)
 }
 
+pub fn query_subdomain_sale(deps: Deps, _env: Env, domain: String) -> StdResult<Binary> {
+    let sale = subdomain_sale_read(deps.storage).may_load(domain.as_bytes())?;
+    to_json_binary(&SubdomainSaleResponse { sale })
+}
+
+pub fn query_subdomain_fuses(deps: Deps, env: Env, name: String) -> StdResult<Binary> {
+    let curr = load_record(deps.storage, &name).map_err(query_error)?;
+    let resp = SubdomainFusesResponse {
+        fuses: active_fuses(deps.storage, &name, &env.block)?,
+        expiration: curr.expiration,
+    };
+    to_json_binary(&resp)
+}
+
+pub fn query_renewal_funds(deps: Deps, _env: Env, name: String) -> StdResult<Binary> {
+    let balance = renewal_funds_read(deps.storage)
+        .may_load(name.as_bytes())?
+        .unwrap_or_default();
+    to_json_binary(&RenewalFundsResponse { balance })
+}
+
+/// query_expiring_between lists names expiring from `from` to `to` (inclusive),
+/// ordered by expiration
+pub fn query_expiring_between(
+    deps: Deps,
+    _env: Env,
+    from: u64,
+    to: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut start = from.to_be_bytes().to_vec();
+    if let Some(name) = start_after {
+        // a name pruned since starts over from the beginning of the window
+        if let Some(record) = resolver_read(deps.storage).may_load(name.as_bytes())? {
+            // first key after start_after's own index entry
+            let mut after = expiration_key(record.expiration, name.as_bytes());
+            after.push(0);
+            if after > start {
+                start = after;
+            }
+        }
+    }
+    let end = to.checked_add(1).map(|end| end.to_be_bytes());
+
+    let names = expiration_index_read(deps.storage)
+        .range(Some(&start), end.as_ref().map(|e| &e[..]), Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, name) = item?;
+            let mut expiration = [0u8; 8];
+            expiration.copy_from_slice(&key[..8]);
+            Ok(ExpiringName {
+                name,
+                expiration: u64::from_be_bytes(expiration),
+            })
+        })
+        .collect::<StdResult<Vec<ExpiringName>>>()?;
+    to_json_binary(&ExpiringBetweenResponse { names })
+}
+
+/// query_names_by_owner lists the names of an owner in the registry's owner index,
+/// tokens transferred on the cw721 contract move once their ownership is synced
+pub fn query_names_by_owner(
+    deps: Deps,
+    env: Env,
+    owner: Addr,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    include_expired: Option<bool>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let include_expired = include_expired.unwrap_or(false);
+    let start = start_after.map(|name| [name.as_bytes(), &[0]].concat());
+
+    let index = owner_index_read(deps.storage, &owner);
+    let entries = index
+        .range(start.as_deref(), None, Order::Ascending)
+        .map(|item| {
+            let (_key, name) = item?;
+            let record = resolver_read(deps.storage).load(name.as_bytes())?;
+            Ok((name, record))
+        });
+    let (names, last_scanned) = scan_names(entries, limit, |_name, record| {
+        include_expired || !record.is_expired(&env.block)
+    })?;
+    to_json_binary(&NamesByOwnerResponse {
+        names,
+        last_scanned,
+    })
+}
+
+/// query_search_prefix lists names starting with a prefix, the prefix is matched
+/// against the ascii form of names, or of subdomain labels when searching a parent
+pub fn query_search_prefix(
+    deps: Deps,
+    env: Env,
+    prefix: String,
+    scope: SearchScope,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    include_expired: Option<bool>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let include_expired = include_expired.unwrap_or(false);
+    let prefix = prefix.to_ascii_lowercase();
+    let end = prefix_range_end(prefix.as_bytes());
+
+    let (names, last_scanned) = match scope {
+        SearchScope::Subdomains { parent } => {
+            // subdomains are indexed by label under their parent
+            let after = start_after.map(|name| match name.split_once('.') {
+                Some((label, _parent)) => label.to_string(),
+                None => name,
+            });
+            let start = range_start(prefix.as_bytes(), after.as_deref());
+            let index = subdomain_index_read(deps.storage, &parent);
+            let entries = index
+                .range(Some(&start), end.as_deref(), Order::Ascending)
+                .map(|item| {
+                    let (_label, name) = item?;
+                    let record = resolver_read(deps.storage).load(name.as_bytes())?;
+                    Ok((name, record))
+                });
+            scan_names(entries, limit, |_name, record| {
+                include_expired || !record.is_expired(&env.block)
+            })?
+        }
+        scope => {
+            let start = range_start(prefix.as_bytes(), start_after.as_deref());
+            let records = resolver_read(deps.storage);
+            let entries = records
+                .range(Some(&start), end.as_deref(), Order::Ascending)
+                .map(|item| {
+                    let (key, record) = item?;
+                    Ok((String::from_utf8(key)?, record))
+                });
+            scan_names(entries, limit, |name, record| {
+                (scope != SearchScope::TopLevel || parent_name(name).is_none())
+                    && (include_expired || !record.is_expired(&env.block))
+            })?
+        }
+    };
+    to_json_binary(&SearchPrefixResponse {
+        names,
+        last_scanned,
+    })
+}
+
+// scan_names lists up to `limit` names passing `keep`, reading at most
+// MAX_SCAN entries. The last name read is returned while entries are left,
+// as the `start_after` for the next page
+fn scan_names(
+    entries: impl Iterator<Item = StdResult<(String, NameRecord)>>,
+    limit: usize,
+    keep: impl Fn(&str, &NameRecord) -> bool,
+) -> StdResult<(Vec<NameSummary>, Option<String>)> {
+    let (records, last_scanned) = scan_records(entries, limit, keep)?;
+    let names = records
+        .into_iter()
+        .map(|(name, record)| NameSummary {
+            unicode_name: to_unicode(&name),
+            name,
+            expiration: record.expiration,
+        })
+        .collect();
+    Ok((names, last_scanned))
+}
+
+// scan_records is scan_names keeping the records read
+fn scan_records(
+    entries: impl Iterator<Item = StdResult<(String, NameRecord)>>,
+    limit: usize,
+    keep: impl Fn(&str, &NameRecord) -> bool,
+) -> StdResult<(Vec<(String, NameRecord)>, Option<String>)> {
+    let mut records = vec![];
+    let mut last_scanned = None;
+    for (scanned, entry) in entries.enumerate() {
+        if records.len() == limit || scanned == MAX_SCAN {
+            return Ok((records, last_scanned));
+        }
+        let (name, record) = entry?;
+        if keep(&name, &record) {
+            records.push((name.clone(), record));
+        }
+        last_scanned = Some(name);
+    }
+    Ok((records, None))
+}
+
+// range_start returns the first key to read for a prefix, after start_after if given
+fn range_start(prefix: &[u8], start_after: Option<&str>) -> Vec<u8> {
+    match start_after.map(|after| [after.as_bytes(), &[0]].concat()) {
+        Some(after) if after.as_slice() > prefix => after,
+        _ => prefix.to_vec(),
+    }
+}
+
+// prefix_range_end returns the first key after every key starting with the prefix,
+// or none if they run to the end of the range
+fn prefix_range_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// query_name_info gathers a name's registry record together with
+/// its cw721 owner and metadata
+pub fn query_name_info(deps: Deps, env: Env, name: String) -> StdResult<Binary> {
+    let c = name_config(deps.storage, &name)?;
+    let record = load_record(deps.storage, &name).map_err(query_error)?;
+    let status = match record.is_expired(&env.block) {
+        false => NameStatus::Active,
+        true if record.in_grace(&env.block, c.grace_period) => NameStatus::InGrace,
+        true => NameStatus::Expired,
+    };
+    let owner = query_owner_of(&deps.querier, &name, &c.cw721)?.owner;
+    let metadata = query_nft_metadata(&deps.querier, &name, &c.cw721)?;
+    let subdomain_count = subdomain_index_read(deps.storage, &name)
+        .range(None, None, Order::Ascending)
+        .count() as u64;
+    let parent = parent_name(&name).map(|(_label, parent)| parent.to_string());
+
+    let resp = NameInfoResponse {
+        unicode_name: to_unicode(&name),
+        name,
+        resolver: record.resolver,
+        created: record.created,
+        expiration: record.expiration,
+        status,
+        owner,
+        metadata,
+        subdomain_count,
+        parent,
+    };
+    to_json_binary(&resp)
+}
+
+pub fn query_availability(
+    deps: Deps,
+    env: Env,
+    parser: &NameParser,
+    name: String,
+) -> StdResult<Binary> {
+    to_json_binary(&name_availability(deps, &env, parser, name)?)
+}
+
+/// query_batch_availability checks up to MAX_LIMIT names in one call
+pub fn query_batch_availability(
+    deps: Deps,
+    env: Env,
+    parser: &NameParser,
+    names: Vec<String>,
+) -> StdResult<Binary> {
+    if names.len() > MAX_LIMIT as usize {
+        return Err(query_error(ContractError::InvalidInput {}));
+    }
+    let names = names
+        .into_iter()
+        .map(|name| name_availability(deps, &env, parser, name))
+        .collect::<StdResult<Vec<AvailabilityResponse>>>()?;
+    to_json_binary(&BatchAvailabilityResponse { names })
+}
+
+// name_availability tells whether a top-level name can be registered,
+// names that can't be parsed or break their tld's naming rules are invalid
+fn name_availability(
+    deps: Deps,
+    env: &Env,
+    parser: &NameParser,
+    input: String,
+) -> StdResult<AvailabilityResponse> {
+    let invalid = |name: String, err: ContractError| AvailabilityResponse {
+        name,
+        availability: Availability::Invalid {
+            reason: err.to_string(),
+        },
+    };
+    let parsed = match parser.parse(&input) {
+        Ok(parsed) => parsed,
+        Err(err) => return Ok(invalid(input, err)),
+    };
+    let name = parsed.name();
+    // subdomains are only created by their domain's owner
+    if parsed.is_subdomain() {
+        let err = ContractError::NameTooDeep {
+            depth: parsed.depth() as u64,
+            max_depth: 1,
+        };
+        return Ok(invalid(name, err));
+    }
+    let checked = registration_config(deps.storage, &name)
+        .and_then(|c| validate_name(&name, &c.name_rules).map(|_| c));
+    let c = match checked {
+        Ok(c) => c,
+        Err(ContractError::Std(err)) => return Err(err),
+        Err(err) => return Ok(invalid(name, err)),
+    };
+    let availability = match resolver_read(deps.storage).may_load(name.as_bytes())? {
+        Some(record) if !record.is_expired(&env.block) => Availability::Taken {
+            until: record.expiration,
+        },
+        Some(record) if record.in_grace(&env.block, c.grace_period) => Availability::InGrace {
+            until: record.expiration.saturating_add(c.grace_period),
+        },
+        _ if reserved_names_read(deps.storage)
+            .may_load(name.as_bytes())?
+            .is_some() =>
+        {
+            Availability::Reserved
+        }
+        _ => Availability::Available,
+    };
+    Ok(AvailabilityResponse { name, availability })
+}
+
+/// query_dns_zone returns a name's DNS records as zone file lines,
+/// records are only served while the name is active and its domain is in DNS mode
+pub fn query_dns_zone(deps: Deps, env: Env, name: String) -> StdResult<Binary> {
+    let record = load_record(deps.storage, &name).map_err(query_error)?;
+    let domain = parent_name(&name).map_or(name.as_str(), |(_label, parent)| parent);
+    let served = !record.is_expired(&env.block)
+        && dns_mode_read(deps.storage)
+            .may_load(domain.as_bytes())?
+            .is_some();
+    let records = match served {
+        true => dns_records_read(deps.storage)
+            .may_load(name.as_bytes())?
+            .unwrap_or_default(),
+        false => vec![],
+    };
+    let origin = format!("{}.", name);
+    let records = records
+        .into_iter()
+        .map(|record| ZoneRecord {
+            name: origin.clone(),
+            ttl: record.ttl,
+            class: String::from("IN"),
+            record_type: record.data.record_type().to_string(),
+            data: record.data.rdata(),
+        })
+        .collect();
+    to_json_binary(&DnsZoneResponse { origin, records })
+}
+
+pub fn query_pending_dispute(deps: Deps, _env: Env, name: String) -> StdResult<Binary> {
+    let dispute = pending_dispute_read(deps.storage).may_load(name.as_bytes())?;
+    to_json_binary(&PendingDisputeResponse { dispute })
+}
+
+/// query_verify_consistency compares registry records against their cw721
+/// tokens and reports the names that have drifted apart, `last_checked`
+/// is the `start_after` for the next page
+pub fn query_verify_consistency(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|name| [name.as_bytes(), &[0]].concat());
+    let records: StdResult<Vec<Record<NameRecord>>> = resolver_read(deps.storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .collect();
+
+    let mut mismatches = vec![];
+    let mut last_checked = None;
+    for (key, record) in records? {
+        let name = String::from_utf8(key)?;
+        let c = name_config(deps.storage, &name)?;
+        let issues = consistency_issues(deps, &c, &name, &record)?;
+        if !issues.is_empty() {
+            mismatches.push(NameMismatch {
+                name: name.clone(),
+                issues,
+            });
+        }
+        last_checked = Some(name);
+    }
+    to_json_binary(&VerifyConsistencyResponse {
+        mismatches,
+        last_checked,
+    })
+}
+
+fn consistency_issues(
+    deps: Deps,
+    c: &Config,
+    name: &str,
+    record: &NameRecord,
+) -> StdResult<Vec<ConsistencyIssue>> {
+    let owner = match query_owner_of(&deps.querier, name, &c.cw721) {
+        Ok(res) => Addr::unchecked(res.owner),
+        Err(_) => return Ok(vec![ConsistencyIssue::MissingToken]),
+    };
+    let mut issues = vec![];
+    let registry_owner = name_owner_read(deps.storage).may_load(name.as_bytes())?;
+    if registry_owner.as_ref() != Some(&owner) {
+        issues.push(ConsistencyIssue::OwnerMismatch {
+            registry: registry_owner,
+            token: owner,
+        });
+    }
+    let metadata = query_nft_metadata(&deps.querier, name, &c.cw721)?;
+    if metadata.expiry != Some(record.expiration) {
+        issues.push(ConsistencyIssue::ExpiryMismatch {
+            registry: record.expiration,
+            token: metadata.expiry,
+        });
+    }
+    if parent_name(name).is_none() {
+        let listed = metadata.subdomains.unwrap_or_default();
+        let expected = subdomain_records(deps.storage, name)?;
+        let listed_as = |label: &String| {
+            listed
+                .iter()
+                .filter(|item| item.name.as_ref() == Some(label))
+                .collect::<Vec<&Subdomain>>()
+        };
+        let mut missing = vec![];
+        let mut stale = vec![];
+        for (label, sub) in expected.iter() {
+            let entries = listed_as(label);
+            if entries.is_empty() {
+                missing.push(label.clone());
+            } else if entries.len() > 1
+                || entries[0].expiry != Some(sub.expiration)
+                || entries[0].resolver.as_ref() != Some(&sub.resolver)
+            {
+                stale.push(label.clone());
+            }
+        }
+        let unknown: Vec<String> = listed
+            .iter()
+            .filter_map(|item| item.name.clone())
+            .filter(|label| !expected.iter().any(|(l, _)| l == label))
+            .collect();
+        if !missing.is_empty() || !stale.is_empty() || !unknown.is_empty() {
+            issues.push(ConsistencyIssue::SubdomainsMismatch {
+                missing,
+                stale,
+                unknown,
+            });
+        }
+    }
+    Ok(issues)
+}
+
+/// subdomain_records loads a domain's subdomain records keyed by label
+pub fn subdomain_records(
+    storage: &dyn Storage,
+    domain: &str,
+) -> StdResult<Vec<(String, NameRecord)>> {
+    subdomain_index_read(storage, domain)
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (label, name) = item?;
+            let record = resolver_read(storage).load(name.as_bytes())?;
+            Ok((String::from_utf8(label)?, record))
+        })
+        .collect()
+}
+
+/// query_subdomains lists a domain's subdomains with their owner and status,
+/// `start_after` is a subdomain label (e.g. `dapp` for `dapp.simpletest.arch`),
+/// as is the `last_scanned` label returned for the next page
+pub fn query_subdomains(
+    deps: Deps,
+    env: Env,
+    domain: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    include_expired: Option<bool>,
+) -> StdResult<Binary> {
+    let c = name_config(deps.storage, &domain)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let include_expired = include_expired.unwrap_or(false);
+    let start = start_after.map(|label| [label.as_bytes(), &[0]].concat());
+
+    let index = subdomain_index_read(deps.storage, &domain);
+    let entries = index
+        .range(start.as_deref(), None, Order::Ascending)
+        .map(|item| {
+            let (_label, name) = item?;
+            let record = resolver_read(deps.storage).load(name.as_bytes())?;
+            Ok((name, record))
+        });
+    let (records, last_scanned) = scan_records(entries, limit, |_name, record| {
+        include_expired || !record.is_expired(&env.block)
+    })?;
+    let subdomains = records
+        .into_iter()
+        .map(|(name, record)| {
+            let status = match record.is_expired(&env.block) {
+                true => SubDomainStatus::ExistingMintExpired,
+                false => SubDomainStatus::ExistingMintActive,
+            };
+            let owner = query_owner_of(&deps.querier, &name, &c.cw721)?.owner;
+            Ok(SubdomainInfo {
+                unicode_name: to_unicode(&name),
+                name,
+                record,
+                owner,
+                status,
+            })
+        })
+        .collect::<StdResult<Vec<SubdomainInfo>>>()?;
+    // pages continue from a label
+    let last_scanned = last_scanned.map(|name| match name.split_once('.') {
+        Some((label, _parent)) => label.to_string(),
+        None => name,
+    });
+    to_json_binary(&SubdomainsResponse {
+        subdomains,
+        last_scanned,
+    })
+}
+
+/// active_fuses returns the fuses burned on a subdomain,
+/// or none if the subdomain has expired
+pub fn active_fuses(storage: &dyn Storage, name: &str, block: &BlockInfo) -> StdResult<u32> {
+    let key = name.as_bytes();
+    match resolver_read(storage).may_load(key)? {
+        // unused bits burned by earlier versions are ignored
+        Some(record) if !record.is_expired(block) => {
+            Ok(subdomain_fuses_read(storage).may_load(key)?.unwrap_or(0) & ALL_FUSES)
+        }
+        _ => Ok(0),
+    }
+}
+
 pub fn query_current_metadata(
     id: &str,
     cw721: &Addr,
     deps: &DepsMut,
+) -> Result<Metadata, ContractError> {
+    query_nft_metadata(&deps.querier, id, cw721).map_err(|err| cw721_query_failed(id, err))
+}
+
+pub fn query_nft_metadata(
+    querier: &QuerierWrapper,
+    id: &str,
+    cw721: &Addr,
 ) -> Result<Metadata, StdError> {
     let query_msg: archid_token::QueryMsg<Extension> = Cw721QueryMsg::NftInfo {
         token_id: id.to_owned(),
     };
     let req = QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: cw721.to_string(),
-        msg: to_json_binary(&query_msg).unwrap(),
+        msg: to_json_binary(&query_msg)?,
     });
-    let res: NftInfoResponse<Metadata> = deps.querier.query(&req)?;
+    let res: NftInfoResponse<Metadata> = querier.query(&req)?;
     Ok(res.extension)
 }
-fn invalid_char(c: char) -> bool {
-    let is_valid = c.is_ascii_digit() || c.is_ascii_lowercase() || (c == '-' || c == '_');
-    !is_valid
+fn cw721_query_failed(name: &str, err: StdError) -> ContractError {
+    ContractError::Cw721QueryFailed {
+        name: name.to_string(),
+        reason: err.to_string(),
+    }
+}
+
+/// query_error turns a contract error into the error returned by queries
+pub fn query_error(err: ContractError) -> StdError {
+    StdError::generic_err(err.to_string())
 }
 
-pub fn is_expired(deps: &DepsMut, key: &[u8], block: &BlockInfo) -> bool {
-    let r = resolver_read(deps.storage).may_load(key).unwrap();
-    match r.is_some() {
-        true => r.unwrap().is_expired(block),
-        _ => true,
+/// load_record returns the record of a name, or an error naming
+/// the missing name or subdomain
+pub fn load_record(storage: &dyn Storage, name: &str) -> Result<NameRecord, ContractError> {
+    match resolver_read(storage).may_load(name.as_bytes())? {
+        Some(record) => Ok(record),
+        None if parent_name(name).is_some() => Err(ContractError::SubdomainNotFound {
+            name: name.to_string(),
+        }),
+        None => Err(ContractError::NameNotExists {
+            name: name.to_string(),
+        }),
     }
 }
 
-/// validate_name returns an error if the name is invalid
-/// (we require 3-64 lowercase ascii letters, numbers, or . - _)
-pub fn validate_name(name: &str) -> Result<(), ContractError> {
-    let length = name.len() as u64;
-    let suffix_index = length as usize - SUFFIX.len();
-    let body = &name[0..suffix_index];
-    if (body.len() as u64) < MIN_NAME_LENGTH {
-        Err(ContractError::NameTooShort {
-            length,
-            min_length: MIN_NAME_LENGTH,
-        })
-    } else if (body.len() as u64) > MAX_NAME_LENGTH {
-        Err(ContractError::NameTooLong {
-            length,
-            max_length: MAX_NAME_LENGTH,
-        })
-    } else {
-        match body.find(invalid_char) {
-            None => Ok(()),
-            Some(bytepos_invalid_char_start) => {
-                let c = name[bytepos_invalid_char_start..].chars().next().unwrap();
-                Err(ContractError::InvalidCharacter { c })
+/// name_config returns the config for the tld of a name,
+/// with the pricing, naming rules and cw721 of that tld
+pub fn name_config(storage: &dyn Storage, name: &str) -> StdResult<Config> {
+    let c = config_read(storage).load()?;
+    match name_tld(name) {
+        TLD => Ok(c),
+        tld => Ok(c.with_tld(&tlds_read(storage).load(tld.as_bytes())?)),
+    }
+}
+
+/// registration_config returns the config for registering a name or subdomain,
+/// or an error if its tld doesn't exist or is disabled
+pub fn registration_config(storage: &dyn Storage, name: &str) -> Result<Config, ContractError> {
+    let c = config_read(storage).load()?;
+    let tld = match name_tld(name) {
+        TLD => {
+            // subdomains of names like `dao.arch` would parse as names of the
+            // `.dao` tld, the same as SetTld refuses tlds shadowing names
+            let label = get_name_body(name.to_string());
+            if tlds_read(storage).may_load(label.as_bytes())?.is_some() {
+                return Err(ContractError::NameShadowsTld {
+                    name: name.to_string(),
+                });
             }
+            return Ok(c);
+        }
+        tld => tld.to_string(),
+    };
+    match tlds_read(storage).may_load(tld.as_bytes())? {
+        Some(t) if t.enabled => Ok(c.with_tld(&t)),
+        Some(_) => Err(ContractError::TldDisabled { tld }),
+        None => Err(ContractError::TldNotExists { tld }),
+    }
+}
+
+pub fn query_cleanup_pool(deps: Deps, _env: Env) -> StdResult<Binary> {
+    let c = config_read(deps.storage).load()?;
+    let balance = cleanup_pool_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    to_json_binary(&CleanupPoolResponse {
+        balance,
+        bounty: c.cleanup_bounty,
+    })
+}
+
+// config with the dispute timelock currently in effect
+pub fn query_config(deps: Deps, env: Env) -> StdResult<Binary> {
+    let mut c = config_read(deps.storage).load()?;
+    c.dispute_timelock = dispute_timelock(deps.storage, &c, &env.block)?;
+    to_json_binary(&c)
+}
+
+/// dispute_timelock returns the timelock new disputes wait out,
+/// which is a lowered timelock once its decrease has taken effect
+pub fn dispute_timelock(storage: &dyn Storage, c: &Config, block: &BlockInfo) -> StdResult<u64> {
+    match timelock_decrease_read(storage).may_load()? {
+        Some(decrease) if block.time.seconds() >= decrease.effective_at => {
+            Ok(decrease.dispute_timelock)
         }
+        _ => Ok(c.dispute_timelock),
+    }
+}
+
+/// query_tlds lists the default tld followed by the tlds added by the admin
+pub fn query_tlds(deps: Deps, _env: Env) -> StdResult<Binary> {
+    let c = config_read(deps.storage).load()?;
+    let mut tlds = vec![TldInfo {
+        tld: TLD.to_string(),
+        config: TldConfig {
+            cw721: c.cw721,
+            base_cost: c.base_cost,
+            base_expiration: c.base_expiration,
+            name_rules: c.name_rules,
+            subdomain_rules: c.subdomain_rules,
+            enabled: true,
+        },
+    }];
+    for item in tlds_read(deps.storage).range(None, None, Order::Ascending) {
+        let (tld, config) = item?;
+        tlds.push(TldInfo {
+            tld: String::from_utf8(tld)?,
+            config,
+        });
+    }
+    to_json_binary(&TldsResponse { tlds })
+}
+
+pub fn invalid_char(c: char) -> bool {
+    let is_valid = c.is_ascii_digit() || c.is_ascii_lowercase() || (c == '-' || c == '_');
+    !is_valid
+}
+
+pub fn is_expired(deps: &DepsMut, key: &[u8], block: &BlockInfo) -> StdResult<bool> {
+    match resolver_read(deps.storage).may_load(key)? {
+        Some(record) => Ok(record.is_expired(block)),
+        None => Ok(true),
+    }
+}
+
+/// validate_name returns an error if a new top-level name
+/// breaks the naming rules
+pub fn validate_name(name: &str, rules: &NamingRules) -> Result<(), ContractError> {
+    validate_top_level(name)?;
+    validate_label(&get_name_body(name.to_string()), rules)
+}
+/// validate_subdomain returns an error if a new subdomain label
+/// breaks the naming rules
+pub fn validate_subdomain(label: &str, rules: &NamingRules) -> Result<(), ContractError> {
+    validate_label(label, rules)
+}
+/// validate_top_level returns an error unless the name is a top-level name,
+/// names that already exist are not checked against the naming rules
+pub fn validate_top_level(name: &str) -> Result<(), ContractError> {
+    let body = match name.rsplit_once('.') {
+        Some((body, tld)) if !body.is_empty() && !tld.is_empty() => body,
+        _ => return Err(ContractError::InvalidInput {}),
+    };
+    match body.chars().find(|c| invalid_char(*c)) {
+        None => Ok(()),
+        Some(c) => Err(ContractError::InvalidCharacter { c }),
     }
 }
-pub fn validate_subdomain(name: &str) -> Result<(), ContractError> {
-    let length = name.len() as u64;
-    if (name.len() as u64) < MIN_NAME_LENGTH {
-        Err(ContractError::NameTooShort {
+// lengths are checked on the ascii form, characters on the unicode form
+fn validate_label(label: &str, rules: &NamingRules) -> Result<(), ContractError> {
+    let length = label.len() as u64;
+    if length < rules.min_length {
+        return Err(ContractError::NameTooShort {
             length,
-            min_length: MIN_NAME_LENGTH,
-        })
-    } else if (name.len() as u64) > MAX_NAME_LENGTH {
-        Err(ContractError::NameTooLong {
+            min_length: rules.min_length,
+        });
+    }
+    if length > rules.max_length {
+        return Err(ContractError::NameTooLong {
             length,
-            max_length: MAX_NAME_LENGTH,
-        })
-    } else {
-        match name.find(invalid_char) {
-            None => Ok(()),
-            Some(bytepos_invalid_char_start) => {
-                let c = name[bytepos_invalid_char_start..].chars().next().unwrap();
-                Err(ContractError::InvalidCharacter { c })
-            }
+            max_length: rules.max_length,
+        });
+    }
+    if let Some(c) = label.chars().find(|c| invalid_char(*c)) {
+        return Err(ContractError::InvalidCharacter { c });
+    }
+    for c in to_unicode(label).chars() {
+        let allowed = match c {
+            '-' => rules.allow_hyphens,
+            '_' => rules.allow_underscores,
+            c if !c.is_ascii() => rules.allow_unicode,
+            _ => true,
+        };
+        if !allowed {
+            return Err(ContractError::InvalidCharacter { c });
         }
     }
+    Ok(())
 }
-pub fn format_name(name: String) -> String {
-    let domain_route = format!("{}{}", name, String::from(SUFFIX));
-    domain_route
-}
+// name without its tld
 pub fn get_name_body(name: String) -> String {
-    let length = name.len() as u64;
-    let suffix_index = length as usize - SUFFIX.len();
-    let body = &name[0..suffix_index];
-    String::from(body)
+    match name.rsplit_once('.') {
+        Some((body, _tld)) => String::from(body),
+        None => name,
+    }
 }
 pub fn get_subdomain_prefix(name: String) -> Option<Vec<String>> {
     let body = get_name_body(name);
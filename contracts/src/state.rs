@@ -1,4 +1,4 @@
-use cosmwasm_std::{Addr, BlockInfo, Storage, Timestamp, Uint128};
+use cosmwasm_std::{Addr, BlockInfo, Decimal, StdResult, Storage, Timestamp, Uint128};
 use cosmwasm_storage::{
     bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton,
     Singleton,
@@ -10,6 +10,46 @@ use serde::{Deserialize, Serialize};
 pub static NAME_RESOLVER_KEY: &[u8] = b"nameresolver";
 pub static SUBDOMAIN_MINTED: &[u8] = b"subdomain_minted";
 pub static CONFIG_KEY: &[u8] = b"config";
+pub static SUBDOMAIN_SALE_KEY: &[u8] = b"subdomain_sale";
+pub static SUBDOMAIN_FUSES_KEY: &[u8] = b"subdomain_fuses";
+pub static RENEWAL_FUNDS_KEY: &[u8] = b"renewal_funds";
+pub static RENEWAL_FUNDS_TOTAL_KEY: &[u8] = b"renewal_funds_total";
+pub static EXPIRATION_INDEX_KEY: &[u8] = b"expiration_index";
+pub static TOP_LEVEL_EXPIRATION_KEY: &[u8] = b"top_level_expiration";
+pub static RENEWAL_EXPIRATION_KEY: &[u8] = b"renewal_expiration";
+pub static SUBDOMAIN_INDEX_KEY: &[u8] = b"subdomain_index";
+pub static NAME_OWNER_KEY: &[u8] = b"name_owner";
+pub static PENDING_DISPUTE_KEY: &[u8] = b"pending_dispute";
+pub static TIMELOCK_DECREASE_KEY: &[u8] = b"timelock_decrease";
+pub static TLD_KEY: &[u8] = b"tld";
+pub static DNS_MODE_KEY: &[u8] = b"dns_mode";
+pub static DNS_RECORDS_KEY: &[u8] = b"dns_records";
+pub static OWNER_INDEX_KEY: &[u8] = b"owner_index";
+pub static CLEANUP_POOL_KEY: &[u8] = b"cleanup_pool";
+pub static RESERVED_NAMES_KEY: &[u8] = b"reserved_names";
+
+// names expiring within 30 days are auto renewed from their escrow
+pub const DEFAULT_RENEWAL_WINDOW: u64 = 2592000;
+// disputes can't be delayed for more than a year
+pub const MAX_DISPUTE_TIMELOCK: u64 = 31536000;
+
+// default naming rules, matching the rules before they were configurable
+pub const DEFAULT_MIN_NAME_LENGTH: u64 = 3;
+pub const DEFAULT_MAX_NAME_LENGTH: u64 = 64;
+pub const DEFAULT_MAX_INTERVALS: u64 = 3;
+// no naming rules can allow labels longer than this
+pub const MAX_LABEL_LENGTH: u64 = 64;
+
+// Subdomain fuses, burned irreversibly by the parent domain owner.
+// Burned fuses hold until the subdomain expires.
+// Bits 1 << 1 and 1 << 3 are unused: subdomain expirations can only ever be
+// extended and subdomains can't have subdomains of their own, so fuses
+// guarding against either could never trigger
+// domain owner can't remove the subdomain
+pub const CANNOT_REMOVE: u32 = 1;
+// domain owner can't remove the subdomain or burn any more fuses
+pub const PARENT_CANNOT_CONTROL: u32 = 1 << 2;
+pub const ALL_FUSES: u32 = CANNOT_REMOVE | PARENT_CANNOT_CONTROL;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
@@ -18,9 +58,121 @@ pub struct Config {
     pub cw721: Addr,
     pub base_cost: Uint128,
     pub base_expiration: u64,
+    // share of each subdomain sale kept by the registry
+    #[serde(default)]
+    pub subdomain_fee: Decimal,
+    // lets anyone pay to renew a name, not only its owner
+    #[serde(default)]
+    pub allow_third_party_renewals: bool,
+    // paid from escrow to whoever processes an auto renewal
+    #[serde(default)]
+    pub keeper_fee: Uint128,
+    // how long (in seconds) before expiry escrowed names get auto renewed,
+    // shorter than the base_expiration of every tld
+    #[serde(default = "default_renewal_window")]
+    pub renewal_window: u64,
+    // how long (in seconds) after expiry only the owner can renew a name
+    #[serde(default)]
+    pub grace_period: u64,
+    // delay (in seconds) before admin revocations and reassignments can be
+    // executed, 0 applies them immediately and MAX_DISPUTE_TIMELOCK is the
+    // longest. Lowering it only takes effect once the previous delay has passed
+    #[serde(default)]
+    pub dispute_timelock: u64,
+    // rules for registering new top-level names
+    #[serde(default)]
+    pub name_rules: NamingRules,
+    // rules for minting new subdomains
+    #[serde(default)]
+    pub subdomain_rules: NamingRules,
+    // paid from the cleanup pool for each expired name pruned
+    #[serde(default)]
+    pub cleanup_bounty: Uint128,
+}
+
+fn default_renewal_window() -> u64 {
+    DEFAULT_RENEWAL_WINDOW
+}
+
+impl Config {
+    // config for names under an additional tld, with the tld's
+    // pricing, naming rules and cw721 in place of the defaults
+    pub fn with_tld(&self, tld: &TldConfig) -> Config {
+        Config {
+            cw721: tld.cw721.clone(),
+            base_cost: tld.base_cost,
+            base_expiration: tld.base_expiration,
+            name_rules: tld.name_rules.clone(),
+            subdomain_rules: tld.subdomain_rules.clone(),
+            ..self.clone()
+        }
+    }
+}
+
+// Additional tlds (e.g. `.dao`) registered by the admin,
+// the default `.arch` tld is configured in Config itself
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TldConfig {
+    // name token collection, the registry must be its minter
+    pub cw721: Addr,
+    pub base_cost: Uint128,
+    pub base_expiration: u64,
+    pub name_rules: NamingRules,
+    pub subdomain_rules: NamingRules,
+    // disabled tlds take no new names or subdomains,
+    // existing names can still be renewed and resolved
+    pub enabled: bool,
+}
+
+impl TldConfig {
+    pub fn is_valid(&self) -> bool {
+        !self.base_cost.is_zero()
+            && self.base_expiration > 0
+            && self.name_rules.is_valid()
+            && self.subdomain_rules.is_valid()
+    }
+}
+
+// NamingRules only apply when a name is registered or a subdomain is minted,
+// so changing them never affects existing names
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NamingRules {
+    // label length in bytes of its ascii (punycode) form
+    pub min_length: u64,
+    pub max_length: u64,
+    // lowercase ascii letters and digits are always allowed
+    pub allow_hyphens: bool,
+    pub allow_underscores: bool,
+    pub allow_unicode: bool,
+    // most base intervals a registration or renewal can cover,
+    // not used for subdomains as their owners set their expiration
+    pub max_intervals: u64,
+}
+
+impl Default for NamingRules {
+    fn default() -> Self {
+        NamingRules {
+            min_length: DEFAULT_MIN_NAME_LENGTH,
+            max_length: DEFAULT_MAX_NAME_LENGTH,
+            allow_hyphens: true,
+            allow_underscores: true,
+            allow_unicode: true,
+            max_intervals: DEFAULT_MAX_INTERVALS,
+        }
+    }
+}
+
+impl NamingRules {
+    pub fn is_valid(&self) -> bool {
+        self.min_length >= 1
+            && self.min_length <= self.max_length
+            && self.max_length <= MAX_LABEL_LENGTH
+            && self.max_intervals >= 1
+    }
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub enum SubDomainStatus {
+    // the rules below can be further restricted by burning subdomain fuses
     // if subdomain in acive mint domain owner can only extend expiration up to domain expiration
     ExistingMintActive,
     // if subdomain expired owner can remint which will first burn existing nft
@@ -36,6 +188,15 @@ pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<Config> {
     singleton_read(storage, CONFIG_KEY)
 }
 
+// Funds paying the bounties for pruning expired names
+pub fn cleanup_pool(storage: &mut dyn Storage) -> Singleton<Uint128> {
+    singleton(storage, CLEANUP_POOL_KEY)
+}
+
+pub fn cleanup_pool_read(storage: &dyn Storage) -> ReadonlySingleton<Uint128> {
+    singleton_read(storage, CLEANUP_POOL_KEY)
+}
+
 /**
     add expiration
     and top level domain?
@@ -50,6 +211,14 @@ impl NameRecord {
     pub fn is_expired(&self, block: &BlockInfo) -> bool {
         Expiration::AtTime(Timestamp::from_seconds(self.expiration)).is_expired(block)
     }
+    // expired, but still reserved for its owner to renew
+    pub fn in_grace(&self, block: &BlockInfo, grace_period: u64) -> bool {
+        self.is_expired(block)
+            && !Expiration::AtTime(Timestamp::from_seconds(
+                self.expiration.saturating_add(grace_period),
+            ))
+            .is_expired(block)
+    }
 }
 pub fn resolver(storage: &mut dyn Storage) -> Bucket<NameRecord> {
     bucket(storage, NAME_RESOLVER_KEY)
@@ -58,3 +227,317 @@ pub fn resolver(storage: &mut dyn Storage) -> Bucket<NameRecord> {
 pub fn resolver_read(storage: &dyn Storage) -> ReadonlyBucket<NameRecord> {
     bucket_read(storage, NAME_RESOLVER_KEY)
 }
+
+// Name records must be saved and removed through save_record / remove_record
+// to keep the expiration and subdomain indexes in sync
+pub fn save_record(storage: &mut dyn Storage, key: &[u8], record: &NameRecord) -> StdResult<()> {
+    if let Some(prev) = resolver_read(storage).may_load(key)? {
+        expiration_index(storage).remove(&expiration_key(prev.expiration, key));
+        top_level_expiration_index(storage).remove(&expiration_key(prev.expiration, key));
+        renewal_expiration_index(storage).remove(&expiration_key(prev.expiration, key));
+    }
+    let name = String::from_utf8(key.to_vec())?;
+    let index_key = expiration_key(record.expiration, key);
+    expiration_index(storage).save(&index_key, &name)?;
+    if renewal_funds_read(storage).may_load(key)?.is_some() {
+        renewal_expiration_index(storage).save(&index_key, &name)?;
+    }
+    match parent_name(&name) {
+        Some((label, parent)) => subdomain_index(storage, parent).save(label.as_bytes(), &name)?,
+        None => top_level_expiration_index(storage).save(&index_key, &name)?,
+    }
+    resolver(storage).save(key, record)
+}
+
+pub fn remove_record(storage: &mut dyn Storage, key: &[u8]) -> StdResult<()> {
+    if let Some(prev) = resolver_read(storage).may_load(key)? {
+        expiration_index(storage).remove(&expiration_key(prev.expiration, key));
+        top_level_expiration_index(storage).remove(&expiration_key(prev.expiration, key));
+        renewal_expiration_index(storage).remove(&expiration_key(prev.expiration, key));
+    }
+    let name = String::from_utf8(key.to_vec())?;
+    if let Some((label, parent)) = parent_name(&name) {
+        subdomain_index(storage, parent).remove(label.as_bytes());
+    }
+    if let Some(owner) = name_owner_read(storage).may_load(key)? {
+        owner_index(storage, &owner).remove(key);
+    }
+    name_owner(storage).remove(key);
+    dns_mode(storage).remove(key);
+    dns_records(storage).remove(key);
+    pending_dispute(storage).remove(key);
+    resolver(storage).remove(key);
+    Ok(())
+}
+
+/// name_tld returns the tld of a stored name, e.g. `arch` for `dapp.simpletest.arch`
+pub fn name_tld(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// parent_name splits a subdomain (e.g. `dapp.simpletest.arch`)
+/// into its label and parent domain, top level names have no parent
+pub fn parent_name(name: &str) -> Option<(&str, &str)> {
+    let (label, parent) = name.split_once('.')?;
+    match parent.contains('.') {
+        true => Some((label, parent)),
+        false => None,
+    }
+}
+
+// Index of names ordered by expiration, keyed by big endian expiration + name
+pub fn expiration_index(storage: &mut dyn Storage) -> Bucket<String> {
+    bucket(storage, EXPIRATION_INDEX_KEY)
+}
+
+pub fn expiration_index_read(storage: &dyn Storage) -> ReadonlyBucket<String> {
+    bucket_read(storage, EXPIRATION_INDEX_KEY)
+}
+
+// Expiration index of top-level names only, so that pruning
+// never has to scan past expired subdomains
+pub fn top_level_expiration_index(storage: &mut dyn Storage) -> Bucket<String> {
+    bucket(storage, TOP_LEVEL_EXPIRATION_KEY)
+}
+
+pub fn top_level_expiration_index_read(storage: &dyn Storage) -> ReadonlyBucket<String> {
+    bucket_read(storage, TOP_LEVEL_EXPIRATION_KEY)
+}
+
+// Expiration index of names with escrowed renewal funds, so that
+// auto renewals never have to scan past names they can't renew
+pub fn renewal_expiration_index(storage: &mut dyn Storage) -> Bucket<String> {
+    bucket(storage, RENEWAL_EXPIRATION_KEY)
+}
+
+pub fn renewal_expiration_index_read(storage: &dyn Storage) -> ReadonlyBucket<String> {
+    bucket_read(storage, RENEWAL_EXPIRATION_KEY)
+}
+
+pub fn expiration_key(expiration: u64, name: &[u8]) -> Vec<u8> {
+    [&expiration.to_be_bytes()[..], name].concat()
+}
+
+// Terms set by a domain owner for selling subdomains of their domain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SubdomainSale {
+    pub price: Uint128,
+    // any denom, the protocol fee is kept in the same denom
+    pub denom: String,
+    // max lifetime (in seconds) a buyer can register a subdomain for
+    pub max_duration: u64,
+}
+
+pub fn subdomain_sale(storage: &mut dyn Storage) -> Bucket<SubdomainSale> {
+    bucket(storage, SUBDOMAIN_SALE_KEY)
+}
+
+pub fn subdomain_sale_read(storage: &dyn Storage) -> ReadonlyBucket<SubdomainSale> {
+    bucket_read(storage, SUBDOMAIN_SALE_KEY)
+}
+
+pub fn subdomain_fuses(storage: &mut dyn Storage) -> Bucket<u32> {
+    bucket(storage, SUBDOMAIN_FUSES_KEY)
+}
+
+pub fn subdomain_fuses_read(storage: &dyn Storage) -> ReadonlyBucket<u32> {
+    bucket_read(storage, SUBDOMAIN_FUSES_KEY)
+}
+
+// Funds escrowed against a name to pay for its auto renewal
+pub fn renewal_funds(storage: &mut dyn Storage) -> Bucket<Uint128> {
+    bucket(storage, RENEWAL_FUNDS_KEY)
+}
+
+pub fn renewal_funds_read(storage: &dyn Storage) -> ReadonlyBucket<Uint128> {
+    bucket_read(storage, RENEWAL_FUNDS_KEY)
+}
+
+// Sum of all escrowed renewal funds, which fee withdrawals can't touch
+pub fn renewal_funds_total(storage: &mut dyn Storage) -> Singleton<Uint128> {
+    singleton(storage, RENEWAL_FUNDS_TOTAL_KEY)
+}
+
+pub fn renewal_funds_total_read(storage: &dyn Storage) -> ReadonlySingleton<Uint128> {
+    singleton_read(storage, RENEWAL_FUNDS_TOTAL_KEY)
+}
+
+// Renewal funds must be saved through save_renewal_funds to keep
+// renewal_funds_total and the renewal expiration index in sync,
+// a zero balance removes the escrow
+pub fn save_renewal_funds(
+    storage: &mut dyn Storage,
+    key: &[u8],
+    balance: Uint128,
+) -> StdResult<()> {
+    let prev = renewal_funds_read(storage)
+        .may_load(key)?
+        .unwrap_or_default();
+    let total = renewal_funds_total_read(storage)
+        .may_load()?
+        .unwrap_or_default()
+        .checked_add(balance)?
+        .checked_sub(prev)?;
+    renewal_funds_total(storage).save(&total)?;
+    if let Some(record) = resolver_read(storage).may_load(key)? {
+        let index_key = expiration_key(record.expiration, key);
+        match balance.is_zero() {
+            true => renewal_expiration_index(storage).remove(&index_key),
+            false => {
+                let name = String::from_utf8(key.to_vec())?;
+                renewal_expiration_index(storage).save(&index_key, &name)?;
+            }
+        }
+    }
+    if balance.is_zero() {
+        renewal_funds(storage).remove(key);
+        Ok(())
+    } else {
+        renewal_funds(storage).save(key, &balance)
+    }
+}
+
+// Subdomains of a domain, keyed by subdomain label
+pub fn subdomain_index<'a>(storage: &'a mut dyn Storage, domain: &str) -> Bucket<'a, String> {
+    Bucket::multilevel(storage, &[SUBDOMAIN_INDEX_KEY, domain.as_bytes()])
+}
+
+pub fn subdomain_index_read<'a>(
+    storage: &'a dyn Storage,
+    domain: &str,
+) -> ReadonlyBucket<'a, String> {
+    ReadonlyBucket::multilevel(storage, &[SUBDOMAIN_INDEX_KEY, domain.as_bytes()])
+}
+
+// Owner of each name's token as last seen by the registry,
+// set on mint and on SyncOwnership through save_name_owner
+pub fn name_owner(storage: &mut dyn Storage) -> Bucket<Addr> {
+    bucket(storage, NAME_OWNER_KEY)
+}
+
+pub fn name_owner_read(storage: &dyn Storage) -> ReadonlyBucket<Addr> {
+    bucket_read(storage, NAME_OWNER_KEY)
+}
+
+// Index of names by the owner in name_owner, keyed by owner + name
+pub fn owner_index<'a>(storage: &'a mut dyn Storage, owner: &Addr) -> Bucket<'a, String> {
+    Bucket::multilevel(storage, &[OWNER_INDEX_KEY, owner.as_bytes()])
+}
+
+pub fn owner_index_read<'a>(storage: &'a dyn Storage, owner: &Addr) -> ReadonlyBucket<'a, String> {
+    ReadonlyBucket::multilevel(storage, &[OWNER_INDEX_KEY, owner.as_bytes()])
+}
+
+// save_name_owner records a name's owner, moving the name
+// out of its previous owner's index
+pub fn save_name_owner(storage: &mut dyn Storage, key: &[u8], owner: &Addr) -> StdResult<()> {
+    if let Some(prev) = name_owner_read(storage).may_load(key)? {
+        owner_index(storage, &prev).remove(key);
+    }
+    let name = String::from_utf8(key.to_vec())?;
+    owner_index(storage, owner).save(key, &name)?;
+    name_owner(storage).save(key, owner)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeAction {
+    Revoke { reason: String },
+    Reassign { new_owner: Addr },
+}
+
+impl DisputeAction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DisputeAction::Revoke { .. } => "revoke",
+            DisputeAction::Reassign { .. } => "reassign",
+        }
+    }
+}
+
+// Admin action waiting out the dispute timelock, can be cancelled until executed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingDispute {
+    pub action: DisputeAction,
+    pub executable_at: u64,
+}
+
+pub fn pending_dispute(storage: &mut dyn Storage) -> Bucket<PendingDispute> {
+    bucket(storage, PENDING_DISPUTE_KEY)
+}
+
+pub fn pending_dispute_read(storage: &dyn Storage) -> ReadonlyBucket<PendingDispute> {
+    bucket_read(storage, PENDING_DISPUTE_KEY)
+}
+
+// Lower dispute timelock set by the admin, waiting out the current timelock
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TimelockDecrease {
+    pub dispute_timelock: u64,
+    pub effective_at: u64,
+}
+
+pub fn timelock_decrease(storage: &mut dyn Storage) -> Singleton<TimelockDecrease> {
+    singleton(storage, TIMELOCK_DECREASE_KEY)
+}
+
+pub fn timelock_decrease_read(storage: &dyn Storage) -> ReadonlySingleton<TimelockDecrease> {
+    singleton_read(storage, TIMELOCK_DECREASE_KEY)
+}
+
+// Top-level names held back by the admin, only the admin can register them
+pub fn reserved_names(storage: &mut dyn Storage) -> Bucket<bool> {
+    bucket(storage, RESERVED_NAMES_KEY)
+}
+
+pub fn reserved_names_read(storage: &dyn Storage) -> ReadonlyBucket<bool> {
+    bucket_read(storage, RESERVED_NAMES_KEY)
+}
+
+pub fn tlds(storage: &mut dyn Storage) -> Bucket<TldConfig> {
+    bucket(storage, TLD_KEY)
+}
+
+pub fn tlds_read(storage: &dyn Storage) -> ReadonlyBucket<TldConfig> {
+    bucket_read(storage, TLD_KEY)
+}
+
+// Top-level domains whose owner opted in to DNS compatible (LDH) labels
+// for the domain and its subdomains
+pub fn dns_mode(storage: &mut dyn Storage) -> Bucket<bool> {
+    bucket(storage, DNS_MODE_KEY)
+}
+
+pub fn dns_mode_read(storage: &dyn Storage) -> ReadonlyBucket<bool> {
+    bucket_read(storage, DNS_MODE_KEY)
+}
+
+// A DNS record of a name, served by DNS gateways while the name is active
+// and its domain is in DNS mode
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DnsRecord {
+    // seconds
+    pub ttl: u32,
+    pub data: DnsRecordData,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsRecordData {
+    A { address: String },
+    Aaaa { address: String },
+    // target host, e.g. `gateway.example.com`
+    Cname { target: String },
+    Txt { text: String },
+    Mx { preference: u16, exchange: String },
+}
+
+// Record set of each name, set by the name's owner and
+// dropped when the name changes owner or is removed
+pub fn dns_records(storage: &mut dyn Storage) -> Bucket<Vec<DnsRecord>> {
+    bucket(storage, DNS_RECORDS_KEY)
+}
+
+pub fn dns_records_read(storage: &dyn Storage) -> ReadonlyBucket<Vec<DnsRecord>> {
+    bucket_read(storage, DNS_RECORDS_KEY)
+}
@@ -0,0 +1,401 @@
+// Multi-test fixtures for contracts building on ArchID, enabled with the
+// `testing` feature. RegistrySuite deploys the registry and archid_token,
+// with the registry as the token minter, e.g.
+//
+//     let alice = Addr::unchecked("alice");
+//     let mut suite = RegistrySuite::builder().with_funds(&alice, 10000).build();
+//     suite.register(&alice, "alice").unwrap();
+//     assert_eq!(suite.resolve("alice.arch").unwrap().address, Some(alice.to_string()));
+use anyhow::Result as AnyResult;
+use cosmwasm_std::{Addr, Coin, Decimal, Empty, Timestamp, Uint128};
+use cw721_updatable::OwnerOfResponse;
+use cw_multi_test::{App, AppResponse, BankSudo, Contract, ContractWrapper, Executor, SudoMsg};
+use serde::de::DeserializeOwned;
+
+use archid_token::{Extension, InstantiateMsg as Cw721InstantiateMsg, QueryMsg as Cw721QueryMsg};
+
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ResolveRecordResponse, TldsResponse};
+use crate::state::{name_tld, Config, NamingRules, TldConfig};
+use crate::write_utils::DENOM;
+
+// seconds per block when advancing time
+const BLOCK_TIME: u64 = 5;
+
+pub fn contract_cw721() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        archid_token::entry::execute,
+        archid_token::entry::instantiate,
+        archid_token::entry::query,
+    );
+    Box::new(contract)
+}
+
+pub fn contract_archid() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_reply(crate::contract::reply);
+    Box::new(contract)
+}
+
+pub struct RegistrySuiteBuilder {
+    admin: Addr,
+    wallet: Addr,
+    base_cost: Uint128,
+    base_expiration: u64,
+    subdomain_fee: Decimal,
+    allow_third_party_renewals: bool,
+    keeper_fee: Uint128,
+    renewal_window: u64,
+    grace_period: u64,
+    dispute_timelock: u64,
+    name_rules: NamingRules,
+    subdomain_rules: NamingRules,
+    cleanup_bounty: Uint128,
+    funds: Vec<(Addr, Uint128)>,
+}
+
+impl Default for RegistrySuiteBuilder {
+    fn default() -> Self {
+        RegistrySuiteBuilder {
+            admin: Addr::unchecked("admin"),
+            wallet: Addr::unchecked("wallet"),
+            base_cost: Uint128::from(5000u128),
+            base_expiration: 86400,
+            subdomain_fee: Decimal::zero(),
+            allow_third_party_renewals: false,
+            keeper_fee: Uint128::zero(),
+            // must be shorter than base_expiration
+            renewal_window: 43200,
+            grace_period: 0,
+            dispute_timelock: 0,
+            name_rules: NamingRules::default(),
+            subdomain_rules: NamingRules::default(),
+            cleanup_bounty: Uint128::zero(),
+            funds: vec![],
+        }
+    }
+}
+
+impl RegistrySuiteBuilder {
+    pub fn with_admin(mut self, admin: &Addr) -> Self {
+        self.admin = admin.clone();
+        self
+    }
+
+    pub fn with_wallet(mut self, wallet: &Addr) -> Self {
+        self.wallet = wallet.clone();
+        self
+    }
+
+    // price and length of one registration interval
+    pub fn with_pricing(mut self, base_cost: u128, base_expiration: u64) -> Self {
+        self.base_cost = Uint128::from(base_cost);
+        self.base_expiration = base_expiration;
+        self
+    }
+
+    pub fn with_subdomain_fee(mut self, subdomain_fee: Decimal) -> Self {
+        self.subdomain_fee = subdomain_fee;
+        self
+    }
+
+    pub fn with_third_party_renewals(mut self, allow: bool) -> Self {
+        self.allow_third_party_renewals = allow;
+        self
+    }
+
+    pub fn with_auto_renewals(mut self, keeper_fee: u128, renewal_window: u64) -> Self {
+        self.keeper_fee = Uint128::from(keeper_fee);
+        self.renewal_window = renewal_window;
+        self
+    }
+
+    pub fn with_grace_period(mut self, grace_period: u64) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    pub fn with_dispute_timelock(mut self, dispute_timelock: u64) -> Self {
+        self.dispute_timelock = dispute_timelock;
+        self
+    }
+
+    pub fn with_name_rules(mut self, rules: NamingRules) -> Self {
+        self.name_rules = rules;
+        self
+    }
+
+    pub fn with_subdomain_rules(mut self, rules: NamingRules) -> Self {
+        self.subdomain_rules = rules;
+        self
+    }
+
+    pub fn with_cleanup_bounty(mut self, bounty: u128) -> Self {
+        self.cleanup_bounty = Uint128::from(bounty);
+        self
+    }
+
+    // mints `amount` aarch to `addr` when the suite is built
+    pub fn with_funds(mut self, addr: &Addr, amount: u128) -> Self {
+        self.funds.push((addr.clone(), Uint128::from(amount)));
+        self
+    }
+
+    pub fn build(self) -> RegistrySuite {
+        let mut app = App::default();
+
+        // the token needs the registry as minter and the registry needs the
+        // token, so the registry is instantiated first and pointed at the
+        // token through UpdateConfig
+        let registry_id = app.store_code(contract_archid());
+        let registry = app
+            .instantiate_contract(
+                registry_id,
+                self.admin.clone(),
+                &InstantiateMsg {
+                    admin: self.admin.clone(),
+                    wallet: self.wallet.clone(),
+                    cw721: self.admin.clone(),
+                    base_cost: self.base_cost,
+                    base_expiration: self.base_expiration,
+                },
+                &[],
+                "ArchID Registry",
+                None,
+            )
+            .unwrap();
+
+        let cw721_id = app.store_code(contract_cw721());
+        let cw721 = app
+            .instantiate_contract(
+                cw721_id,
+                registry.clone(),
+                &Cw721InstantiateMsg {
+                    name: "ArchID".to_string(),
+                    symbol: "AID".to_string(),
+                    minter: registry.to_string(),
+                },
+                &[],
+                "ArchID Cw721 Token",
+                None,
+            )
+            .unwrap();
+
+        let config = Config {
+            admin: self.admin.clone(),
+            wallet: self.wallet.clone(),
+            cw721: cw721.clone(),
+            base_cost: self.base_cost,
+            base_expiration: self.base_expiration,
+            subdomain_fee: self.subdomain_fee,
+            allow_third_party_renewals: self.allow_third_party_renewals,
+            keeper_fee: self.keeper_fee,
+            renewal_window: self.renewal_window,
+            grace_period: self.grace_period,
+            dispute_timelock: self.dispute_timelock,
+            name_rules: self.name_rules,
+            subdomain_rules: self.subdomain_rules,
+            cleanup_bounty: self.cleanup_bounty,
+        };
+        app.execute_contract(
+            self.admin.clone(),
+            registry.clone(),
+            &ExecuteMsg::UpdateConfig { config },
+            &[],
+        )
+        .unwrap();
+
+        let mut suite = RegistrySuite {
+            app,
+            registry,
+            cw721,
+            admin: self.admin,
+            wallet: self.wallet,
+        };
+        for (addr, amount) in self.funds {
+            suite.mint_native(&addr, amount.u128());
+        }
+        suite
+    }
+}
+
+pub struct RegistrySuite {
+    pub app: App,
+    pub registry: Addr,
+    pub cw721: Addr,
+    pub admin: Addr,
+    pub wallet: Addr,
+}
+
+impl RegistrySuite {
+    pub fn new() -> Self {
+        RegistrySuiteBuilder::default().build()
+    }
+
+    pub fn builder() -> RegistrySuiteBuilder {
+        RegistrySuiteBuilder::default()
+    }
+
+    pub fn block_time(&self) -> u64 {
+        self.app.block_info().time.seconds()
+    }
+
+    pub fn advance_time(&mut self, seconds: u64) {
+        self.app.update_block(|block| {
+            block.time = block.time.plus_seconds(seconds);
+            block.height += seconds / BLOCK_TIME + 1;
+        });
+    }
+
+    pub fn set_time(&mut self, time: u64) {
+        let current = self.block_time();
+        let mut block = self.app.block_info();
+        block.height += time.saturating_sub(current) / BLOCK_TIME + 1;
+        block.time = Timestamp::from_seconds(time);
+        self.app.set_block(block);
+    }
+
+    pub fn mint_native(&mut self, addr: &Addr, amount: u128) {
+        self.app
+            .sudo(SudoMsg::Bank(BankSudo::Mint {
+                to_address: addr.to_string(),
+                amount: vec![Coin {
+                    denom: String::from(DENOM),
+                    amount: Uint128::from(amount),
+                }],
+            }))
+            .unwrap();
+    }
+
+    pub fn balance(&self, addr: &Addr) -> Uint128 {
+        self.app.wrap().query_balance(addr, DENOM).unwrap().amount
+    }
+
+    pub fn config(&self) -> Config {
+        self.query(&QueryMsg::Config {}).unwrap()
+    }
+
+    // adds `tld` with default naming rules and a new name token collection,
+    // returning the collection
+    pub fn add_tld(&mut self, tld: &str, base_cost: u128, base_expiration: u64) -> AnyResult<Addr> {
+        let cw721_id = self.app.store_code(contract_cw721());
+        let cw721 = self.app.instantiate_contract(
+            cw721_id,
+            self.registry.clone(),
+            &Cw721InstantiateMsg {
+                name: format!("ArchID .{}", tld),
+                symbol: "AID".to_string(),
+                minter: self.registry.to_string(),
+            },
+            &[],
+            "ArchID Cw721 Token",
+            None,
+        )?;
+        let admin = self.admin.clone();
+        self.execute(
+            &admin,
+            &ExecuteMsg::SetTld {
+                tld: tld.to_string(),
+                config: TldConfig {
+                    cw721: cw721.clone(),
+                    base_cost: Uint128::from(base_cost),
+                    base_expiration,
+                    name_rules: NamingRules::default(),
+                    subdomain_rules: NamingRules::default(),
+                    enabled: true,
+                },
+            },
+            &[],
+        )?;
+        Ok(cw721)
+    }
+
+    // config of the tld of a name, names without a known tld are under `.arch`
+    pub fn tld_config(&self, name: &str) -> TldConfig {
+        let res: TldsResponse = self.query(&QueryMsg::Tlds {}).unwrap();
+        let tld = name_tld(name);
+        match res
+            .tlds
+            .iter()
+            .position(|info| info.tld.eq_ignore_ascii_case(tld))
+        {
+            Some(i) => res.tlds[i].config.clone(),
+            None => res.tlds[0].config.clone(),
+        }
+    }
+
+    pub fn execute(
+        &mut self,
+        sender: &Addr,
+        msg: &ExecuteMsg,
+        funds: &[Coin],
+    ) -> AnyResult<AppResponse> {
+        self.app
+            .execute_contract(sender.clone(), self.registry.clone(), msg, funds)
+    }
+
+    // registers `name` (`{name}.arch` if it has no tld) for one interval,
+    // paid from the sender's balance
+    pub fn register(&mut self, sender: &Addr, name: &str) -> AnyResult<AppResponse> {
+        let cost = self.tld_config(name).base_cost;
+        self.execute(
+            sender,
+            &ExecuteMsg::Register {
+                name: name.to_string(),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: cost,
+            }],
+        )
+    }
+
+    // renews `name` for one interval, paid from the sender's balance
+    pub fn renew(&mut self, sender: &Addr, name: &str) -> AnyResult<AppResponse> {
+        let cost = self.tld_config(name).base_cost;
+        self.execute(
+            sender,
+            &ExecuteMsg::RenewRegistration {
+                name: name.to_string(),
+            },
+            &[Coin {
+                denom: String::from(DENOM),
+                amount: cost,
+            }],
+        )
+    }
+
+    pub fn query<T: DeserializeOwned>(&self, msg: &QueryMsg) -> AnyResult<T> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(self.registry.clone(), msg)?)
+    }
+
+    // name including its tld
+    pub fn resolve(&self, name: &str) -> AnyResult<ResolveRecordResponse> {
+        self.query(&QueryMsg::ResolveRecord {
+            name: name.to_string(),
+        })
+    }
+
+    // token owner of a name including its tld
+    pub fn owner_of(&self, name: &str) -> AnyResult<Addr> {
+        let cw721 = self.tld_config(name).cw721;
+        let msg: Cw721QueryMsg<Extension> = Cw721QueryMsg::OwnerOf {
+            token_id: name.to_string(),
+            include_expired: None,
+        };
+        let res: OwnerOfResponse = self.app.wrap().query_wasm_smart(cw721, &msg)?;
+        Ok(Addr::unchecked(res.owner))
+    }
+}
+
+impl Default for RegistrySuite {
+    fn default() -> Self {
+        RegistrySuite::new()
+    }
+}
@@ -1,15 +1,22 @@
 use cosmwasm_std::{
-    to_json_binary, Addr, BankMsg, Coin, CosmosMsg, DepsMut, Env, StdResult, Uint128, WasmMsg,
+    to_json_binary, Addr, BankMsg, Coin, CosmosMsg, DepsMut, Env, StdResult, SubMsg, Uint128,
+    WasmMsg,
 };
 
+use crate::error::ContractError;
+use crate::idn::to_unicode;
 use crate::read_utils::get_name_body;
-use crate::read_utils::query_current_metadata;
-use crate::state::{resolver, NameRecord};
+use crate::read_utils::{load_record, query_current_metadata, subdomain_records};
+use crate::state::{
+    dns_records, parent_name, pending_dispute, save_name_owner, save_record, NameRecord,
+};
 use archid_token::{
     ExecuteMsg as Cw721ExecuteMsg, Metadata, MintMsg, Subdomain, UpdateMetadataMsg,
 };
 
 pub static DENOM: &str = "aarch";
+// reply id of burns that may fail, see burn_submsg
+pub const BURN_REPLY_ID: u64 = 1;
 
 #[allow(clippy::too_many_arguments)]
 pub fn add_subdomain_metadata(
@@ -20,9 +27,11 @@ pub fn add_subdomain_metadata(
     resolver: Addr,
     created: u64,
     expiry: u64,
-) -> StdResult<CosmosMsg> {
-    let mut current_metadata: Metadata = query_current_metadata(&name, cw721, deps).unwrap();
-    let mut subdomains: Vec<Subdomain> = current_metadata.subdomains.as_ref().unwrap().clone();
+) -> Result<CosmosMsg, ContractError> {
+    let mut current_metadata: Metadata = query_current_metadata(&name, cw721, deps)?;
+    let mut subdomains: Vec<Subdomain> = current_metadata.subdomains.unwrap_or_default();
+    // a reminted subdomain replaces the entry of its previous mint
+    subdomains.retain(|item| item.name.as_ref() != Some(&subdomain));
     subdomains.push(Subdomain {
         name: Some(subdomain),
         resolver: Some(resolver),
@@ -42,16 +51,27 @@ pub fn update_subdomain_metadata(
     subdomain: &String,
     resolver: Addr,
     expiry: u64,
-) -> StdResult<CosmosMsg> {
-    let mut current_metadata: Metadata = query_current_metadata(domain, cw721, deps).unwrap();
-    let mut subdomains: Vec<Subdomain> = current_metadata.subdomains.as_ref().unwrap().clone();
-    let index = subdomains
+) -> Result<CosmosMsg, ContractError> {
+    let mut current_metadata: Metadata = query_current_metadata(domain, cw721, deps)?;
+    let mut subdomains: Vec<Subdomain> = current_metadata.subdomains.unwrap_or_default();
+    match subdomains
         .iter()
-        .position(|r| &r.clone().name.unwrap() == subdomain)
-        .unwrap();
-    subdomains[index].expiry = Some(expiry);
-    subdomains[index].minted = None;
-    subdomains[index].resolver = Some(resolver);
+        .position(|r| r.name.as_ref() == Some(subdomain))
+    {
+        Some(index) => {
+            subdomains[index].expiry = Some(expiry);
+            subdomains[index].minted = None;
+            subdomains[index].resolver = Some(resolver);
+        }
+        // restore entries that went missing from the domain's metadata
+        None => subdomains.push(Subdomain {
+            name: Some(subdomain.clone()),
+            resolver: Some(resolver),
+            minted: None,
+            created: None,
+            expiry: Some(expiry),
+        }),
+    }
     current_metadata.subdomains = Some((*subdomains).to_vec());
     let resp = send_data_update(domain, cw721, current_metadata)?;
     Ok(resp)
@@ -62,17 +82,17 @@ pub fn update_subdomain_expiry(
     domain: String,
     subdomain: String,
     expiration: u64,
-) -> StdResult<Vec<CosmosMsg>> {
+) -> Result<Vec<CosmosMsg>, ContractError> {
     let mut messages = Vec::new();
     let domain_route: String = format!("{}.{}", subdomain, domain);
     let key = domain_route.as_bytes();
-    let domain_config: NameRecord = (resolver(deps.storage).may_load(key)?).unwrap();
+    let domain_config: NameRecord = load_record(deps.storage, &domain_route)?;
     let record = NameRecord {
         resolver: domain_config.resolver.clone(),
         created: domain_config.created,
         expiration,
     };
-    resolver(deps.storage).save(key, &record)?;
+    save_record(deps.storage, key, &record)?;
     let msg = update_subdomain_metadata(
         &deps,
         &nft,
@@ -90,11 +110,11 @@ pub fn remove_subdomain_metadata(
     cw721: &Addr,
     name: String,
     subdomain: String,
-) -> StdResult<CosmosMsg> {
-    let mut current_metadata: Metadata = query_current_metadata(&name, cw721, deps).unwrap();
-    let mut subdomains = current_metadata.subdomains.as_ref().unwrap().clone();
+) -> Result<CosmosMsg, ContractError> {
+    let mut current_metadata: Metadata = query_current_metadata(&name, cw721, deps)?;
+    let mut subdomains = current_metadata.subdomains.unwrap_or_default();
 
-    subdomains.retain(|item| item.name.as_ref().unwrap().as_bytes() != subdomain.as_bytes());
+    subdomains.retain(|item| item.name.as_ref() != Some(&subdomain));
     current_metadata.subdomains = Some((*subdomains).to_vec());
     let resp = send_data_update(&name, cw721, current_metadata)?;
     Ok(resp)
@@ -123,15 +143,18 @@ pub fn mint_handler(
     } else {
         Some(vec![])
     };
+    // name and description show the unicode form,
+    // domain keeps the ascii form used as token id
+    let display_name = to_unicode(name);
     let description = if body.contains('.') {
-        [name, " subdomain"].concat()
+        [&display_name, " subdomain"].concat()
     } else {
-        [name, " domain"].concat()
+        [&display_name, " domain"].concat()
     };
 
     let mint_extension = Some(Metadata {
         description: Some(description),
-        name: Some(body),
+        name: Some(to_unicode(&body)),
         image: None,
         created: Some(created),
         expiry: Some(expiration),
@@ -170,11 +193,24 @@ pub fn burn_handler(name: &String, cw721: &Addr) -> StdResult<CosmosMsg> {
     Ok(resp)
 }
 
+// burn_submsg burns a token that may already have been burned on the cw721
+// contract directly, in which case the failed burn is only recorded by reply
+pub fn burn_submsg(name: &String, cw721: &Addr) -> StdResult<SubMsg> {
+    Ok(SubMsg::reply_on_error(
+        burn_handler(name, cw721)?,
+        BURN_REPLY_ID,
+    ))
+}
+
 pub fn send_tokens(to: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    send_funds(to, DENOM, amount)
+}
+
+pub fn send_funds(to: &Addr, denom: &str, amount: Uint128) -> StdResult<CosmosMsg> {
     let msg = BankMsg::Send {
         to_address: to.into(),
         amount: ([Coin {
-            denom: String::from(DENOM),
+            denom: String::from(denom),
             amount,
         }])
         .to_vec(),
@@ -182,6 +218,41 @@ pub fn send_tokens(to: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
     Ok(msg.into())
 }
 
+/// reconcile_metadata rewrites a name's expiry, and for domains the
+/// list of subdomains, from the registry's records
+pub fn reconcile_metadata(
+    deps: &DepsMut,
+    cw721: &Addr,
+    name: &String,
+    record: &NameRecord,
+) -> Result<CosmosMsg, ContractError> {
+    let mut current_metadata: Metadata = query_current_metadata(name, cw721, deps)?;
+    current_metadata.expiry = Some(record.expiration);
+    if parent_name(name).is_none() {
+        let previous = current_metadata.subdomains.unwrap_or_default();
+        let subdomains = subdomain_records(deps.storage, name)?
+            .into_iter()
+            .map(|(label, sub)| Subdomain {
+                minted: previous
+                    .iter()
+                    .find(|item| item.name.as_ref() == Some(&label))
+                    .and_then(|item| item.minted),
+                name: Some(label),
+                resolver: Some(sub.resolver),
+                created: Some(sub.created),
+                expiry: Some(sub.expiration),
+            })
+            .collect();
+        current_metadata.subdomains = Some(subdomains);
+    }
+    Ok(send_data_update(name, cw721, current_metadata)?)
+}
+// clear_owner_records drops the metadata set by a name's previous owner
+pub fn clear_owner_records(metadata: &mut Metadata) {
+    metadata.image = None;
+    metadata.accounts = metadata.accounts.take().map(|_| vec![]);
+    metadata.websites = metadata.websites.take().map(|_| vec![]);
+}
 pub fn send_data_update(name: &String, cw721: &Addr, data: Metadata) -> StdResult<CosmosMsg> {
     let update = Cw721ExecuteMsg::UpdateMetadata(UpdateMetadataMsg {
         token_id: name.to_string(),
@@ -200,8 +271,8 @@ pub fn update_metadata_expiry(
     cw721: &Addr,
     name: String,
     expiration: u64,
-) -> StdResult<CosmosMsg> {
-    let mut current_metadata: Metadata = query_current_metadata(&name, cw721, &deps).unwrap();
+) -> Result<CosmosMsg, ContractError> {
+    let mut current_metadata: Metadata = query_current_metadata(&name, cw721, &deps)?;
     current_metadata.expiry = Some(expiration);
     let resp = send_data_update(&name, cw721, current_metadata)?;
     Ok(resp)
@@ -217,7 +288,7 @@ pub fn register_new_subdomain(
     new_resolver: Addr,
     new_owner: Addr,
     expiration: u64,
-) -> StdResult<Vec<CosmosMsg>> {
+) -> Result<Vec<CosmosMsg>, ContractError> {
     let domain_route: String = format!("{}.{}", subdomain, domain);
     let key = domain_route.as_bytes();
     let mut messages = Vec::new();
@@ -238,7 +309,8 @@ pub fn register_new_subdomain(
         created,
         expiration,
     };
-    resolver(deps.storage).save(key, &record)?;
+    save_record(deps.storage, key, &record)?;
+    save_name_owner(deps.storage, key, &new_owner)?;
 
     let resp = mint_handler(&domain_route, &new_owner, &nft, created, expiration)?;
     messages.push(resp);
@@ -257,13 +329,16 @@ pub fn burn_remint_subdomain(
     new_resolver: Addr,
     new_owner: Addr,
     expiration: u64,
-) -> StdResult<Vec<CosmosMsg>> {
+) -> Result<Vec<CosmosMsg>, ContractError> {
     let domain_route: String = format!("{}.{}", subdomain, domain);
     let key = domain_route.as_bytes();
     let mut messages = Vec::new();
     let created = env.block.time.seconds();
     let burn_msg = burn_handler(&format!("{}.{}", subdomain, domain), &nft)?;
     messages.push(burn_msg);
+    // records and disputes of the expired subdomain belong to its previous owner
+    dns_records(deps.storage).remove(key);
+    pending_dispute(deps.storage).remove(key);
 
     let metadata_msg = add_subdomain_metadata(
         &deps,
@@ -280,7 +355,8 @@ pub fn burn_remint_subdomain(
         created,
         expiration,
     };
-    resolver(deps.storage).save(key, &record)?;
+    save_record(deps.storage, key, &record)?;
+    save_name_owner(deps.storage, key, &new_owner)?;
 
     let resp = mint_handler(&domain_route, &new_owner, &nft, created, expiration)?;
     messages.push(resp);